@@ -0,0 +1,98 @@
+//! Compare the cost of `log_error!` when the event is enabled and when it's filtered out.
+//!
+//! Run it with `cargo bench`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tracing::Level;
+use tracing_log_error::log_error;
+
+/// An error with a deep chain of sources and a large `Debug` representation.
+#[derive(Debug)]
+struct Expensive {
+    payload: Vec<u64>,
+    source: Option<Box<Expensive>>,
+}
+
+impl Expensive {
+    fn new(depth: usize) -> Self {
+        Self {
+            payload: (0..100).collect(),
+            source: (depth > 0).then(|| Box::new(Expensive::new(depth - 1))),
+        }
+    }
+}
+
+impl std::fmt::Display for Expensive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Expensive error with {} values", self.payload.len())
+    }
+}
+
+impl std::error::Error for Expensive {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|e| e as _)
+    }
+}
+
+/// A subscriber that's only interested in `ERROR` events, and discards them.
+struct ErrorsOnly;
+
+impl tracing::Subscriber for ErrorsOnly {
+    fn enabled(&self, metadata: &tracing::Metadata<'_>) -> bool {
+        *metadata.level() <= Level::ERROR
+    }
+
+    fn new_span(&self, _span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        tracing::span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        struct Visitor;
+
+        impl tracing::field::Visit for Visitor {
+            fn record_debug(
+                &mut self,
+                _field: &tracing::field::Field,
+                value: &dyn std::fmt::Debug,
+            ) {
+                black_box(format!("{:?}", value));
+            }
+        }
+
+        event.record(&mut Visitor);
+    }
+
+    fn enter(&self, _span: &tracing::span::Id) {}
+
+    fn exit(&self, _span: &tracing::span::Id) {}
+}
+
+fn bench(name: &str, f: impl Fn()) {
+    const ITERATIONS: u32 = 10_000;
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed: Duration = start.elapsed();
+    println!("{:<10} {:>10.0?}/iter", name, elapsed / ITERATIONS);
+}
+
+fn main() {
+    let e = Expensive::new(10);
+    tracing::subscriber::with_default(ErrorsOnly, || {
+        bench(
+            "enabled",
+            || log_error!(black_box(&e), level: Level::ERROR, "Yay"),
+        );
+        bench(
+            "disabled",
+            || log_error!(black_box(&e), level: Level::WARN, "Yay"),
+        );
+    });
+}
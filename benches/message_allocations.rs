@@ -0,0 +1,134 @@
+//! Count the allocations performed to record `error.message`, comparing
+//! `fields::error_message` with a pre-formatted `String`, both for an error and for
+//! a message assembled on the spot.
+//!
+//! Run it with `cargo bench --bench message_allocations`.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use tracing::field::{Field, Visit};
+use tracing::Level;
+use tracing_log_error::fields;
+
+/// Counts the allocations performed by the program.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// An error whose `Display` representation is a static string.
+#[derive(Debug)]
+struct Static(&'static str);
+
+impl std::fmt::Display for Static {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for Static {}
+
+/// A subscriber that formats every field into a reusable buffer, so that it doesn't
+/// allocate on its own once the buffer is large enough.
+struct Formatting {
+    buffer: Mutex<String>,
+}
+
+impl tracing::Subscriber for Formatting {
+    fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        tracing::span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        struct Visitor<'a>(&'a mut String);
+
+        impl Visit for Visitor<'_> {
+            fn record_debug(&mut self, _field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.clear();
+                let _ = write!(self.0, "{:?}", value);
+                black_box(&self.0);
+            }
+        }
+
+        event.record(&mut Visitor(&mut self.buffer.lock().unwrap()));
+    }
+
+    fn enter(&self, _span: &tracing::span::Id) {}
+
+    fn exit(&self, _span: &tracing::span::Id) {}
+}
+
+fn count(name: &str, f: impl Fn()) {
+    const ITERATIONS: usize = 10_000;
+
+    // Warm up, e.g. to register the callsite and to grow the subscriber's buffer.
+    f();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "{:<14} {:>5.2} allocations/event",
+        name,
+        allocations as f64 / ITERATIONS as f64
+    );
+}
+
+fn main() {
+    let e = Static("The connection was dropped");
+    let subscriber = Formatting {
+        buffer: Mutex::new(String::with_capacity(1024)),
+    };
+    tracing::subscriber::with_default(subscriber, || {
+        count("display", || {
+            tracing::event!(
+                Level::ERROR,
+                { fields::ERROR_MESSAGE } = fields::error_message(black_box(&e)),
+            )
+        });
+        count("pre-formatted", || {
+            tracing::event!(
+                Level::ERROR,
+                { fields::ERROR_MESSAGE } = black_box(&e).to_string(),
+            )
+        });
+        count("format_args!", || {
+            tracing::event!(
+                Level::ERROR,
+                { fields::ERROR_MESSAGE } =
+                    fields::error_message(format_args!("{} ({})", black_box(&e), 42)),
+            )
+        });
+        count("format!", || {
+            tracing::event!(
+                Level::ERROR,
+                { fields::ERROR_MESSAGE } = format!("{} ({})", black_box(&e), 42),
+            )
+        });
+    });
+}
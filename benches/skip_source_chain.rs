@@ -0,0 +1,106 @@
+//! Compare the cost of `log_error!` with and without `skip_source_chain: true`, for an
+//! error with a deep chain of sources, and check that the sources aren't walked at all.
+//!
+//! Run it with `cargo bench --bench skip_source_chain`.
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing_log_error::log_error;
+
+static SOURCE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// An error with a deep chain of sources, counting the calls to `Error::source`.
+#[derive(Debug)]
+struct Nested {
+    depth: usize,
+    source: Option<Box<Nested>>,
+}
+
+impl Nested {
+    fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            source: (depth > 0).then(|| Box::new(Nested::new(depth - 1))),
+        }
+    }
+}
+
+impl std::fmt::Display for Nested {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Nested error at depth {}", self.depth)
+    }
+}
+
+impl std::error::Error for Nested {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        SOURCE_CALLS.fetch_add(1, Ordering::Relaxed);
+        self.source.as_deref().map(|e| e as _)
+    }
+}
+
+/// A subscriber that formats every field, then discards it.
+struct Formatting;
+
+impl tracing::Subscriber for Formatting {
+    fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        tracing::span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        struct Visitor;
+
+        impl tracing::field::Visit for Visitor {
+            fn record_debug(
+                &mut self,
+                _field: &tracing::field::Field,
+                value: &dyn std::fmt::Debug,
+            ) {
+                black_box(format!("{:?}", value));
+            }
+        }
+
+        event.record(&mut Visitor);
+    }
+
+    fn enter(&self, _span: &tracing::span::Id) {}
+
+    fn exit(&self, _span: &tracing::span::Id) {}
+}
+
+fn bench(name: &str, f: impl Fn()) {
+    const ITERATIONS: u32 = 10_000;
+
+    SOURCE_CALLS.store(0, Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed: Duration = start.elapsed();
+    let source_calls = SOURCE_CALLS.load(Ordering::Relaxed) as f64 / f64::from(ITERATIONS);
+    println!(
+        "{:<20} {:>10.0?}/iter {:>6.1} source() calls/iter",
+        name,
+        elapsed / ITERATIONS,
+        source_calls
+    );
+}
+
+fn main() {
+    let e = Nested::new(32);
+    tracing::subscriber::with_default(Formatting, || {
+        bench("source chain", || log_error!(black_box(&e), "Yay"));
+        bench(
+            "skip_source_chain",
+            || log_error!(black_box(&e), skip_source_chain: true, "Yay"),
+        );
+    });
+}
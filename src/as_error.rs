@@ -0,0 +1,341 @@
+//! Turn the error passed to [`log_error!`](crate::log_error) into something that
+//! implements [`std::error::Error`].
+//!
+//! Most errors are passed through as they are.
+//! Boxed errors (e.g. `Box<dyn Error + Send + Sync>`) are converted to the error they box.
+//! With the `anyhow` feature enabled, `anyhow::Error` is converted to the
+//! `dyn Error` it wraps, whose sources are the ones tracked by `anyhow::Error::chain`.
+//!
+//! The kinds also compute the fields that can't be derived from the `dyn Error` alone,
+//! such as the backtrace that `anyhow` captured when the error was created.
+//!
+//! The dispatch relies on method resolution preferring the candidate that requires
+//! the fewest auto-references: the tags must be in scope and `__log_error_kind` must be
+//! invoked on a reference to a [`Wrap`], see [`__log_error_as_error!`](crate::__log_error_as_error).
+//!
+//! The same technique is used to record the classification of an error if it implements
+//! [`ErrorClassification`](crate::ErrorClassification), see [`ClassifiedTag`], to record
+//! the OS error code of I/O errors, see [`OsCodeTag`], and by
+//! [`log_failure!`](crate::log_failure) to only record
+//! the `Debug` representation of a failure if it has one, see [`DebugTag`].
+//!
+//! Not part of the public API.
+
+/// Wraps a reference to the error passed to `log_error!`, to pick the right kind for it.
+pub struct Wrap<'a, T: ?Sized>(pub &'a T);
+
+/// Implemented by all types that implement [`std::error::Error`].
+///
+/// It provides a clear diagnostic when `log_error!` is invoked with something that
+/// isn't an error, rather than a trait-bound error for each of the error fields.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't implement `core::error::Error`",
+    label = "expected a type implementing `core::error::Error`",
+    note = "if it dereferences to a type that implements `core::error::Error` (e.g. `Rc<dyn Error>`), use `*` to dereference it"
+)]
+pub trait IsError {
+    fn __log_error_as_dyn(&self) -> &(dyn core::error::Error + '_);
+}
+
+impl<E: core::error::Error> IsError for E {
+    fn __log_error_as_dyn(&self) -> &(dyn core::error::Error + '_) {
+        self
+    }
+}
+
+impl IsError for dyn core::error::Error + '_ {
+    fn __log_error_as_dyn(&self) -> &(dyn core::error::Error + '_) {
+        self
+    }
+}
+
+impl IsError for dyn core::error::Error + Send + '_ {
+    fn __log_error_as_dyn(&self) -> &(dyn core::error::Error + '_) {
+        self
+    }
+}
+
+impl IsError for dyn core::error::Error + Send + Sync + '_ {
+    fn __log_error_as_dyn(&self) -> &(dyn core::error::Error + '_) {
+        self
+    }
+}
+
+/// Check, at compile-time, that `e` is an error.
+pub fn assert_error<E: IsError + ?Sized>(e: &E) -> &E {
+    e
+}
+
+/// Used by [`log_error_if!`](crate::log_error_if): passing the predicate through a function
+/// lets the compiler infer the type of the closure's argument.
+pub fn check_predicate<E: ?Sized>(e: &E, predicate: impl FnOnce(&E) -> bool) -> bool {
+    predicate(e)
+}
+
+/// The fallback: the wrapped value must implement [`std::error::Error`] and it's used as is.
+pub trait ErrorTag {
+    fn __log_error_kind(&self) -> ErrorKind {
+        ErrorKind
+    }
+}
+
+impl<T: ?Sized> ErrorTag for &Wrap<'_, T> {}
+
+pub struct ErrorKind;
+
+impl ErrorKind {
+    pub fn __log_error_as_error<E: IsError + ?Sized>(
+        self,
+        e: &E,
+    ) -> &(dyn core::error::Error + '_) {
+        assert_error(e).__log_error_as_dyn()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn __log_error_backtrace<E: IsError + ?Sized>(self, e: &E) -> Option<String> {
+        crate::fields::_error_backtrace(self.__log_error_as_error(e))
+    }
+
+    #[cfg(feature = "std")]
+    pub fn __log_error_backtrace_or_source_chain<E: IsError + ?Sized>(self, e: &E) -> String {
+        let e = self.__log_error_as_error(e);
+        crate::fields::_error_backtrace(e)
+            .unwrap_or_else(|| crate::fields::_error_source_chain(e, usize::MAX))
+    }
+}
+
+/// `Box<dyn Error + Send + Sync>` (and friends) don't implement [`std::error::Error`],
+/// but they dereference to a type that does.
+/// They're handled here, rather than via `*`, so that the box isn't moved.
+pub trait BoxedTag {
+    fn __log_error_kind(&self) -> BoxedKind {
+        BoxedKind
+    }
+}
+
+impl<E: IsError + ?Sized> BoxedTag for Wrap<'_, alloc::boxed::Box<E>> {}
+
+impl<E: IsError + ?Sized> BoxedTag for Wrap<'_, &alloc::boxed::Box<E>> {}
+
+pub struct BoxedKind;
+
+impl BoxedKind {
+    pub fn __log_error_as_error<E: IsError + ?Sized>(
+        self,
+        e: &alloc::boxed::Box<E>,
+    ) -> &(dyn core::error::Error + '_) {
+        ErrorKind.__log_error_as_error(&**e)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn __log_error_backtrace<E: IsError + ?Sized>(
+        self,
+        e: &alloc::boxed::Box<E>,
+    ) -> Option<String> {
+        ErrorKind.__log_error_backtrace(&**e)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn __log_error_backtrace_or_source_chain<E: IsError + ?Sized>(
+        self,
+        e: &alloc::boxed::Box<E>,
+    ) -> String {
+        ErrorKind.__log_error_backtrace_or_source_chain(&**e)
+    }
+}
+
+/// `anyhow::Error` doesn't implement [`std::error::Error`], but it dereferences to a
+/// type that does.
+#[cfg(feature = "anyhow")]
+pub trait AnyhowTag {
+    fn __log_error_kind(&self) -> AnyhowKind {
+        AnyhowKind
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl AnyhowTag for Wrap<'_, anyhow::Error> {}
+
+#[cfg(feature = "anyhow")]
+impl AnyhowTag for Wrap<'_, &anyhow::Error> {}
+
+#[cfg(feature = "anyhow")]
+pub struct AnyhowKind;
+
+#[cfg(feature = "anyhow")]
+impl AnyhowKind {
+    pub fn __log_error_as_error(
+        self,
+        e: &anyhow::Error,
+    ) -> &(dyn core::error::Error + Send + Sync + 'static) {
+        e.as_ref()
+    }
+
+    pub fn __log_error_backtrace(self, e: &anyhow::Error) -> Option<String> {
+        crate::fields::anyhow_backtrace(e)
+    }
+
+    pub fn __log_error_backtrace_or_source_chain(self, e: &anyhow::Error) -> String {
+        let source_chain = || {
+            let e: &(dyn core::error::Error + 'static) = e.as_ref();
+            crate::fields::_error_source_chain(e, usize::MAX)
+        };
+        crate::fields::anyhow_backtrace(e).unwrap_or_else(source_chain)
+    }
+}
+
+/// Failures that implement `Debug` get an `error.details` field.
+pub trait DebugTag {
+    fn __log_failure_kind(&self) -> DebugKind {
+        DebugKind
+    }
+}
+
+impl<T: core::fmt::Debug + ?Sized> DebugTag for Wrap<'_, T> {}
+
+pub struct DebugKind;
+
+impl DebugKind {
+    pub fn __log_failure_details<T: core::fmt::Debug + ?Sized>(
+        self,
+        e: &T,
+    ) -> impl tracing::Value + '_ {
+        crate::fields::error_details(e)
+    }
+}
+
+/// The fallback: failures that don't implement `Debug` don't get an `error.details` field.
+pub trait NoDebugTag {
+    fn __log_failure_kind(&self) -> NoDebugKind {
+        NoDebugKind
+    }
+}
+
+impl<T: ?Sized> NoDebugTag for &Wrap<'_, T> {}
+
+pub struct NoDebugKind;
+
+impl NoDebugKind {
+    pub fn __log_failure_details<T: ?Sized>(self, _e: &T) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Errors that implement [`ErrorClassification`](crate::ErrorClassification) get
+/// classification fields.
+pub trait ClassifiedTag {
+    fn __log_error_classification_kind(&self) -> ClassifiedKind {
+        ClassifiedKind
+    }
+}
+
+impl<T: crate::ErrorClassification + ?Sized> ClassifiedTag for Wrap<'_, T> {}
+
+pub struct ClassifiedKind;
+
+impl ClassifiedKind {
+    pub fn __log_error_retryable<T: crate::ErrorClassification + ?Sized>(
+        self,
+        e: &T,
+    ) -> Option<bool> {
+        crate::fields::error_retryable(e)
+    }
+}
+
+/// The fallback: errors that don't implement
+/// [`ErrorClassification`](crate::ErrorClassification) don't get classification fields.
+pub trait UnclassifiedTag {
+    fn __log_error_classification_kind(&self) -> UnclassifiedKind {
+        UnclassifiedKind
+    }
+}
+
+impl<T: ?Sized> UnclassifiedTag for &Wrap<'_, T> {}
+
+pub struct UnclassifiedKind;
+
+impl UnclassifiedKind {
+    pub fn __log_error_retryable<T: ?Sized>(self, _e: &T) -> Option<bool> {
+        None
+    }
+}
+
+/// I/O errors, or trait objects that might be I/O errors, get an `error.os_code` field.
+///
+/// Unlike the other tags, it computes the field right away: the value doesn't
+/// borrow from the error.
+pub trait OsCodeTag {
+    fn __log_error_os_code(&self) -> Option<i32>;
+}
+
+#[cfg(feature = "std")]
+impl OsCodeTag for Wrap<'_, std::io::Error> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code(self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl OsCodeTag for Wrap<'_, &std::io::Error> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code(self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl OsCodeTag for Wrap<'_, dyn core::error::Error + 'static> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code_dyn(self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl OsCodeTag for Wrap<'_, dyn core::error::Error + Send + 'static> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code_dyn(self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl OsCodeTag for Wrap<'_, dyn core::error::Error + Send + Sync + 'static> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code_dyn(self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl OsCodeTag for Wrap<'_, Box<dyn core::error::Error + 'static>> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code_dyn(&**self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl OsCodeTag for Wrap<'_, Box<dyn core::error::Error + Send + Sync + 'static>> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code_dyn(&**self.0)
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl OsCodeTag for Wrap<'_, anyhow::Error> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code_dyn(self.0.as_ref())
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl OsCodeTag for Wrap<'_, &anyhow::Error> {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        crate::fields::error_os_code_dyn((*self.0).as_ref())
+    }
+}
+
+/// The fallback: other errors can't be I/O errors, they don't get an `error.os_code` field.
+pub trait NoOsCodeTag {
+    fn __log_error_os_code(&self) -> Option<i32> {
+        None
+    }
+}
+
+impl<T: ?Sized> NoOsCodeTag for &Wrap<'_, T> {}
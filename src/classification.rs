@@ -0,0 +1,56 @@
+/// Classify errors, to drive retry and alerting logic from your logs.
+///
+/// [`log_error!`](crate::log_error) records the classification of an error if its type
+/// implements this trait:
+///
+/// ```rust
+/// use tracing_log_error::{log_error, ErrorClassification};
+///
+/// #[derive(Debug)]
+/// enum DbError {
+///     Timeout,
+///     InvalidQuery,
+/// }
+///
+/// impl std::fmt::Display for DbError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         match self {
+///             DbError::Timeout => f.write_str("The query timed out"),
+///             DbError::InvalidQuery => f.write_str("The query is invalid"),
+///         }
+///     }
+/// }
+///
+/// impl std::error::Error for DbError {}
+///
+/// impl ErrorClassification for DbError {
+///     fn is_retryable(&self) -> Option<bool> {
+///         Some(matches!(self, DbError::Timeout))
+///     }
+/// }
+///
+/// // Records `error.retryable = true`
+/// log_error!(DbError::Timeout, "Failed to fetch the user");
+/// ```
+///
+/// For all other errors, as well as when a method returns `None`, the corresponding
+/// field is omitted.
+pub trait ErrorClassification {
+    /// Whether the operation that failed is worth retrying, e.g. because the error is
+    /// transient. Recorded in the `error.retryable` field.
+    fn is_retryable(&self) -> Option<bool> {
+        None
+    }
+}
+
+impl<T: ErrorClassification + ?Sized> ErrorClassification for &T {
+    fn is_retryable(&self) -> Option<bool> {
+        (**self).is_retryable()
+    }
+}
+
+impl<T: ErrorClassification + ?Sized> ErrorClassification for alloc::boxed::Box<T> {
+    fn is_retryable(&self) -> Option<bool> {
+        (**self).is_retryable()
+    }
+}
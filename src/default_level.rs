@@ -0,0 +1,151 @@
+#[cfg(feature = "runtime-default-level")]
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use tracing::Level;
+
+#[cfg(feature = "runtime-default-level")]
+static DEFAULT_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// Change the level used by [`log_error!`](crate::log_error) when no `level:` is specified.
+///
+/// It defaults to `ERROR`.
+/// It comes in handy to tone down error logs while the application is running in a
+/// known, degraded mode, without touching every call site:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::{log_error, set_default_level};
+///
+/// set_default_level(Level::WARN);
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Emitted at the `WARN` level
+/// log_error!(e, "The connection was dropped");
+/// // Emitted at the `ERROR` level: an explicit level always wins
+/// log_error!(e, level: Level::ERROR, "The connection was dropped");
+/// # set_default_level(Level::ERROR);
+/// ```
+///
+/// The change is global and takes effect immediately, on all threads.
+///
+/// Requires the `runtime-default-level` feature.
+///
+/// # Cost
+///
+/// Since the default level is only known at runtime, every invocation without `level:`
+/// expands to five `tracing::event!` callsites, one per level, matched against
+/// [`default_level`]. Each callsite is a separate static, with its own metadata and
+/// interest cache, and adds to the size of the binary.
+/// That's why it's opt-in: without the feature, those invocations expand to a single
+/// `ERROR` callsite.
+#[cfg(feature = "runtime-default-level")]
+pub fn set_default_level(level: Level) {
+    DEFAULT_LEVEL.store(encode(level), Ordering::Relaxed);
+}
+
+/// The level used by [`log_error!`](crate::log_error) when no `level:` is specified,
+/// see [`set_default_level`].
+#[cfg(feature = "runtime-default-level")]
+pub fn default_level() -> Level {
+    decode(DEFAULT_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Parse a level from a configuration value, e.g. to feed it to `level:` or to
+/// `set_default_level`:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::{level_from_config, log_error};
+///
+/// let configured = " Warn ";
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(
+///     e,
+///     level: level_from_config(configured).unwrap_or(Level::ERROR),
+///     "The connection was dropped"
+/// );
+/// ```
+///
+/// It's more lenient than `Level`'s `FromStr` implementation:
+///
+/// - The comparison is case-insensitive, and leading and trailing whitespace is ignored.
+/// - `warning` is accepted as an alias of `warn`, `err` as an alias of `error`.
+///
+/// It returns `None` for anything else, including the numeric levels (`1` to `5`)
+/// accepted by `FromStr`: they're easy to get backwards in a configuration file.
+pub fn level_from_config(s: &str) -> Option<Level> {
+    let s = s.trim();
+    [
+        ("error", Level::ERROR),
+        ("err", Level::ERROR),
+        ("warn", Level::WARN),
+        ("warning", Level::WARN),
+        ("info", Level::INFO),
+        ("debug", Level::DEBUG),
+        ("trace", Level::TRACE),
+    ]
+    .into_iter()
+    .find(|(name, _)| name.eq_ignore_ascii_case(s))
+    .map(|(_, level)| level)
+}
+
+#[cfg(feature = "runtime-default-level")]
+fn encode(level: Level) -> u8 {
+    match level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        _ => 4,
+    }
+}
+
+#[cfg(feature = "runtime-default-level")]
+fn decode(level: u8) -> Level {
+    match level {
+        0 => Level::ERROR,
+        1 => Level::WARN,
+        2 => Level::INFO,
+        3 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "runtime-default-level")]
+    #[test]
+    fn roundtrip() {
+        // The other tests rely on the default level: we don't touch the global here.
+        assert_eq!(decode(0), Level::ERROR);
+        for level in [
+            Level::TRACE,
+            Level::DEBUG,
+            Level::INFO,
+            Level::WARN,
+            Level::ERROR,
+        ] {
+            assert_eq!(decode(encode(level)), level);
+        }
+    }
+
+    #[test]
+    fn from_config() {
+        for (s, level) in [
+            ("error", Level::ERROR),
+            ("ERROR", Level::ERROR),
+            ("Err", Level::ERROR),
+            ("warn", Level::WARN),
+            ("Warning", Level::WARN),
+            (" info\n", Level::INFO),
+            ("DeBuG", Level::DEBUG),
+            ("trace", Level::TRACE),
+        ] {
+            assert_eq!(level_from_config(s), Some(level), "{:?}", s);
+        }
+        for s in ["", "  ", "fatal", "warnings", "1", "5", "info!", "in fo"] {
+            assert_eq!(level_from_config(s), None, "{:?}", s);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Emit events whose set of fields is only known at runtime.
+//!
+//! `tracing` expects the fields of an event to be known at compile-time: they are part
+//! of the `static` metadata generated by `tracing::event!` for each callsite.
+//! To work around this limitation, we create (and leak) a dedicated callsite for each
+//! combination of level, target, location and field names we encounter at runtime.
+//! The number of such combinations is expected to be small and bounded, so the leak is
+//! too.
+use std::collections::HashMap;
+use std::panic::Location;
+use std::sync::{Mutex, OnceLock};
+
+use tracing::callsite::{Callsite, Identifier};
+use tracing::field::{Field, FieldSet, Value};
+use tracing::level_filters::{LevelFilter, STATIC_MAX_LEVEL};
+use tracing::metadata::Kind;
+use tracing::subscriber::Interest;
+use tracing::{Dispatch, Event, Level, Metadata};
+
+/// The maximum number of fields that can be recorded on a single dynamic event.
+/// Additional fields are discarded.
+pub(crate) const MAX_FIELDS: usize = 256;
+
+struct DynamicCallsite {
+    metadata: OnceLock<Metadata<'static>>,
+}
+
+impl Callsite for DynamicCallsite {
+    fn set_interest(&self, _interest: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata
+            .get()
+            .expect("The metadata of a dynamic callsite is set before it is registered")
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct CallsiteKey {
+    level: Level,
+    target: String,
+    location: &'static Location<'static>,
+    field_names: Vec<String>,
+}
+
+/// Retrieve the metadata for the given combination of level, target, location and
+/// field names, creating (and registering) a new callsite if needed.
+fn metadata(
+    level: Level,
+    target: &str,
+    location: &'static Location<'static>,
+    field_names: &[&str],
+) -> &'static Metadata<'static> {
+    static CALLSITES: OnceLock<Mutex<HashMap<CallsiteKey, &'static DynamicCallsite>>> =
+        OnceLock::new();
+
+    let key = CallsiteKey {
+        level,
+        target: target.to_owned(),
+        location,
+        field_names: field_names.iter().map(|s| (*s).to_owned()).collect(),
+    };
+    let mut callsites = CALLSITES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(callsite) = callsites.get(&key) {
+        return callsite.metadata.get().unwrap();
+    }
+
+    let callsite: &'static DynamicCallsite = Box::leak(Box::new(DynamicCallsite {
+        metadata: OnceLock::new(),
+    }));
+    let names: Vec<&'static str> = key
+        .field_names
+        .iter()
+        .map(|name| &*Box::leak(name.clone().into_boxed_str()))
+        .collect();
+    let metadata = Metadata::new(
+        "event (dynamic)",
+        Box::leak(key.target.clone().into_boxed_str()),
+        level,
+        Some(location.file()),
+        Some(location.line()),
+        None,
+        FieldSet::new(Box::leak(names.into_boxed_slice()), Identifier(callsite)),
+        Kind::EVENT,
+    );
+    let _ = callsite.metadata.set(metadata);
+    tracing::callsite::register(callsite);
+    callsites.insert(key, callsite);
+    callsite.metadata.get().unwrap()
+}
+
+/// A cheap, conservative check: `false` if no subscriber can be interested in an event
+/// at the given level.
+pub(crate) fn level_enabled(level: Level) -> bool {
+    level <= STATIC_MAX_LEVEL && level <= LevelFilter::current()
+}
+
+/// Emit an event with the given fields.
+///
+/// If the same field name appears more than once, the last value wins.
+pub(crate) fn emit(
+    level: Level,
+    target: &str,
+    location: &'static Location<'static>,
+    fields: &[(&str, &dyn Value)],
+) {
+    if !level_enabled(level) {
+        return;
+    }
+
+    let mut deduped: Vec<(&str, &dyn Value)> = Vec::with_capacity(fields.len());
+    for &(name, value) in fields {
+        match deduped.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = value,
+            None => deduped.push((name, value)),
+        }
+    }
+    deduped.truncate(MAX_FIELDS);
+    if deduped.is_empty() {
+        return;
+    }
+
+    let names: Vec<&str> = deduped.iter().map(|(name, _)| *name).collect();
+    let metadata = metadata(level, target, location, &names);
+    tracing::dispatcher::get_default(|dispatch| {
+        if !dispatch.enabled(metadata) {
+            return;
+        }
+        let fieldset = metadata.fields();
+        let fields: Vec<Field> = fieldset.iter().collect();
+        let values: Vec<&dyn Value> = deduped.iter().map(|(_, value)| *value).collect();
+        // `tracing` only accepts arrays of a length known at compile-time when building
+        // a `ValueSet`, hence the bucketing.
+        match fields.len() {
+            0..=16 => dispatch_event::<16>(dispatch, metadata, &fields, &values),
+            17..=64 => dispatch_event::<64>(dispatch, metadata, &fields, &values),
+            _ => dispatch_event::<MAX_FIELDS>(dispatch, metadata, &fields, &values),
+        }
+    });
+}
+
+fn dispatch_event<const N: usize>(
+    dispatch: &Dispatch,
+    metadata: &'static Metadata<'static>,
+    fields: &[Field],
+    values: &[&dyn Value],
+) {
+    // Unused slots are filled with an empty value for the first field, which
+    // subscribers will skip over.
+    let entries: [(&Field, Option<&dyn Value>); N] = std::array::from_fn(|i| match fields.get(i) {
+        Some(field) => (field, Some(values[i])),
+        None => (&fields[0], None),
+    });
+    dispatch.event(&Event::new(
+        metadata,
+        &metadata.fields().value_set(&entries),
+    ));
+}
@@ -0,0 +1,162 @@
+use alloc::string::String;
+use core::fmt::Display;
+
+use tracing::Level;
+
+use crate::fields;
+
+/// A snapshot of the fields recorded by [`log_error!`](crate::log_error) for an error.
+///
+/// The fields are computed once, when the report is created: you can then log them
+/// and reuse them elsewhere (e.g. in the body of an HTTP response) without walking
+/// the error again.
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::ErrorReport;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let report = ErrorReport::new(&e);
+/// report.log(Level::WARN, "The request failed");
+///
+/// let body = format!("Something went wrong: {}", report.message());
+/// ```
+///
+/// It mirrors the shape of what gets logged, so it's also a good starting point to
+/// ship errors to another service: copy the fields into your own wire type, and the
+/// receiving end sees the same representation as your logs.
+///
+/// ```rust
+/// use tracing_log_error::ErrorReport;
+///
+/// struct WireError {
+///     message: String,
+///     details: String,
+///     source_chain: String,
+/// }
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let report = ErrorReport::from(&e as &dyn std::error::Error);
+/// let wire = WireError {
+///     message: report.message().to_owned(),
+///     details: report.details().to_owned(),
+///     source_chain: report.source_chain().to_owned(),
+/// };
+/// ```
+///
+/// The event is emitted from within this crate: its target is
+/// `tracing_log_error::error_report`, rather than the module you called `log` from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReport {
+    message: String,
+    details: String,
+    source_chain: String,
+    type_name: &'static str,
+}
+
+impl ErrorReport {
+    /// Capture the fields of the given error.
+    pub fn new<E: core::error::Error + ?Sized>(e: &E) -> Self {
+        Self::with_type_name(e, fields::error_type_name(e))
+    }
+
+    /// For callers that know the type of the error better than `E` does, e.g. when
+    /// `E` is a trait object.
+    pub(crate) fn with_type_name<E: core::error::Error + ?Sized>(
+        e: &E,
+        type_name: &'static str,
+    ) -> Self {
+        Self {
+            message: alloc::format!("{}", fields::Redacted(e)),
+            details: alloc::format!("{:?}", fields::Redacted(e)),
+            source_chain: fields::_error_source_chain(e, usize::MAX),
+            type_name,
+        }
+    }
+
+    /// The value of the [`ERROR_MESSAGE`](fields::ERROR_MESSAGE) field.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The value of the [`ERROR_DETAILS`](fields::ERROR_DETAILS) field.
+    pub fn details(&self) -> &str {
+        &self.details
+    }
+
+    /// The value of the [`ERROR_SOURCE_CHAIN`](fields::ERROR_SOURCE_CHAIN) field.
+    pub fn source_chain(&self) -> &str {
+        &self.source_chain
+    }
+
+    /// The value of the [`ERROR_TYPE`](fields::ERROR_TYPE) field.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Emit an event at the given level, with the captured fields and the given message.
+    pub fn log(&self, level: Level, message: impl Display) {
+        macro_rules! log {
+            ($lvl:expr) => {
+                tracing::event!(
+                    $lvl,
+                    { fields::ERROR_MESSAGE } = self.message.as_str(),
+                    { fields::ERROR_DETAILS } = self.details.as_str(),
+                    { fields::ERROR_SOURCE_CHAIN } = self.source_chain.as_str(),
+                    { fields::ERROR_TYPE } = self.type_name,
+                    "{}",
+                    message
+                )
+            };
+        }
+
+        // `tracing` requires the level of an event to be known at compile-time,
+        // hence the branching.
+        match level {
+            Level::ERROR => log!(Level::ERROR),
+            Level::WARN => log!(Level::WARN),
+            Level::INFO => log!(Level::INFO),
+            Level::DEBUG => log!(Level::DEBUG),
+            _ => log!(Level::TRACE),
+        }
+    }
+}
+
+impl<E: core::error::Error + ?Sized> From<&E> for ErrorReport {
+    fn from(e: &E) -> Self {
+        Self::new(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::with_captured;
+
+    #[test]
+    fn report() {
+        let e = std::io::Error::other("My error");
+        let report = ErrorReport::new(&e);
+        assert_eq!(report.message(), "My error");
+        assert_eq!(report.details(), format!("{:?}", e));
+        assert_eq!(report.source_chain(), "");
+        assert_eq!(report.type_name(), "std::io::error::Error");
+
+        let events = with_captured(|| {
+            report.log(Level::WARN, "The request failed");
+            report.clone().log(Level::TRACE, "The request failed");
+        });
+        assert_eq!(events.len(), 2);
+        let (level, fields) = &events[0];
+        assert_eq!(*level, Level::WARN);
+        assert_eq!(fields["message"], "The request failed");
+        assert_eq!(fields[fields::ERROR_MESSAGE], "My error");
+        assert_eq!(fields[fields::ERROR_DETAILS], report.details());
+        assert_eq!(fields[fields::ERROR_TYPE], "std::io::error::Error");
+        assert_eq!(events[1].0, Level::TRACE);
+
+        let boxed: Box<dyn std::error::Error> = Box::new(e);
+        let from_dyn = ErrorReport::from(&*boxed);
+        assert_eq!(from_dyn.message(), "My error");
+    }
+}
@@ -7,46 +7,2513 @@
 //!
 //! For example, you have [`ERROR_MESSAGE`] and [`error_message`] for the
 //! `error.message` field.
+//!
+//! # OpenTelemetry
+//!
+//! If the `otel` feature is enabled, the field names follow the
+//! [OpenTelemetry semantic conventions for exceptions](https://opentelemetry.io/docs/specs/semconv/exceptions/exceptions-spans/)
+//! instead — e.g. `exception.message` rather than `error.message`.
+//! Check out the documentation of each constant for the name it maps to.
+//!
+//! # Redaction
+//!
+//! Errors sometimes embed sensitive data, e.g. a connection string with a password.
+//! Use [`set_redactor`] to register a function that scrubs the message, the details,
+//! the source chain and the root cause of every error before they're recorded.
+//!
+//! # Control characters
+//!
+//! Control characters in the representation of an error (e.g. an ANSI escape sequence,
+//! or a NUL byte picked up from a file path) can corrupt terminals and log pipelines.
+//! They are escaped before being recorded—e.g. `\u{1b}` for the escape character—except
+//! for line breaks (`\n`) and tabs (`\t`).
+//! The values are always valid UTF-8: bytes that aren't (e.g. in a Unix file path) are
+//! either replaced with `U+FFFD` or escaped, depending on how the error formats them.
+//!
+//! # Panicking `Display` and `Debug` implementations
+//!
+//! If the `Display` or `Debug` implementation of an error panics, so does the logging
+//! call that formats it.
+//! Enable the `catch-format-panics` feature to catch the panic instead: the message,
+//! the details, the source chain and the root cause fall back to a
+//! `<panicked while formatting>` placeholder (`PANICKED_WHILE_FORMATTING`).
+//!
+//! The panic hook still runs, so the panic is reported as usual (e.g. on `stderr`).
+//! Nothing can be caught if your binary is built with `panic = "abort"`.
+//! With the feature enabled, errors are formatted into an intermediate `String`
+//! before being recorded, rather than straight into the subscriber's output.
+//!
+//! # `no_std`
+//!
+//! Without the `std` feature, the functions that deal with types from `std`
+//! (e.g. [`std::io::Error`] or [`std::backtrace::Backtrace`]) aren't available,
+//! and neither are [`set_redactor`] and [`set_fingerprint_normalizer`].
+//! Everything else only requires `alloc`.
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::backtrace::{Backtrace, BacktraceStatus};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
 use tracing::Value;
 
 /// The field name to record the `Display` representation of an error.
 ///
 /// Use [`error_message`] to populate the field.
-pub const ERROR_MESSAGE: &str = "error.message";
+///
+/// It's `exception.message` if the `otel` feature is enabled.
+pub const ERROR_MESSAGE: &str = if cfg!(feature = "otel") {
+    "exception.message"
+} else {
+    "error.message"
+};
 
 /// The field name to record the `Debug` representation of an error.
 ///
 /// Use [`error_message`] to populate the field.
-pub const ERROR_DETAILS: &str = "error.details";
+///
+/// It's `exception.details` if the `otel` feature is enabled.
+pub const ERROR_DETAILS: &str = if cfg!(feature = "otel") {
+    "exception.details"
+} else {
+    "error.details"
+};
+
+/// The field name to record that the value in [`ERROR_DETAILS`] was truncated.
+///
+/// Use [`TruncatedDetails::truncated`] to populate the field.
+///
+/// It's `exception.details_truncated` if the `otel` feature is enabled.
+pub const ERROR_DETAILS_TRUNCATED: &str = if cfg!(feature = "otel") {
+    "exception.details_truncated"
+} else {
+    "error.details_truncated"
+};
 
 /// The field name to record the chain of sources for an error.
 ///
 /// Use [`error_source_chain`] to populate the field.
-pub const ERROR_SOURCE_CHAIN: &str = "error.source_chain";
+///
+/// It's `exception.stacktrace` if the `otel` feature is enabled, since the
+/// source chain is the closest thing to a stacktrace we have when no backtrace
+/// was captured.
+pub const ERROR_SOURCE_CHAIN: &str = if cfg!(feature = "otel") {
+    "exception.stacktrace"
+} else {
+    "error.source_chain"
+};
+
+/// The field name to record the Rust type name of an error.
+///
+/// Use [`error_type_name`] to populate the field.
+///
+/// It's `exception.type` if the `otel` feature is enabled.
+pub const ERROR_TYPE: &str = if cfg!(feature = "otel") {
+    "exception.type"
+} else {
+    "error.type"
+};
+
+/// The field name to record a backtrace for an error.
+///
+/// Use [`error_backtrace`] to populate the field.
+///
+/// It's `exception.stacktrace` if the `otel` feature is enabled—the same name
+/// as [`ERROR_SOURCE_CHAIN`]. Use [`error_backtrace_or_source_chain`] to populate
+/// it in that case.
+pub const ERROR_BACKTRACE: &str = if cfg!(feature = "otel") {
+    "exception.stacktrace"
+} else {
+    "error.backtrace"
+};
+
+/// The field name to record the deepest source of an error.
+///
+/// Use [`error_root_cause`] to populate the field.
+///
+/// It's `exception.root_cause` if the `otel` feature is enabled.
+pub const ERROR_ROOT_CAUSE: &str = if cfg!(feature = "otel") {
+    "exception.root_cause"
+} else {
+    "error.root_cause"
+};
+
+/// The field name to record the first line of the `Display` representation of an error.
+///
+/// Use [`error_summary`] to populate the field.
+///
+/// It's `exception.summary` if the `otel` feature is enabled.
+pub const ERROR_SUMMARY: &str = if cfg!(feature = "otel") {
+    "exception.summary"
+} else {
+    "error.summary"
+};
+
+/// The field name to record an error as a single JSON object.
+///
+/// Use `error_json` to populate the field, it requires the `json` feature.
+///
+/// It's `exception` if the `otel` feature is enabled.
+pub const ERROR_JSON: &str = if cfg!(feature = "otel") {
+    "exception"
+} else {
+    "error"
+};
+
+/// The field name to record the number of sources in the chain of an error.
+///
+/// Use [`error_chain_depth`] to populate the field.
+///
+/// It's `exception.chain_depth` if the `otel` feature is enabled.
+pub const ERROR_CHAIN_DEPTH: &str = if cfg!(feature = "otel") {
+    "exception.chain_depth"
+} else {
+    "error.chain_depth"
+};
+
+/// The field names to record the sources of an error one by one, starting from the
+/// error's direct source: `error.source_0`, `error.source_1`, etc.
+///
+/// Use [`error_sources_indexed`] to populate the fields.
+/// At most [`MAX_INDEXED_SOURCES`] sources can be recorded this way.
+///
+/// They're `exception.source_<n>` if the `otel` feature is enabled.
+pub const ERROR_SOURCES_INDEXED: [&str; MAX_INDEXED_SOURCES] = if cfg!(feature = "otel") {
+    [
+        "exception.source_0",
+        "exception.source_1",
+        "exception.source_2",
+        "exception.source_3",
+        "exception.source_4",
+        "exception.source_5",
+        "exception.source_6",
+        "exception.source_7",
+    ]
+} else {
+    [
+        "error.source_0",
+        "error.source_1",
+        "error.source_2",
+        "error.source_3",
+        "error.source_4",
+        "error.source_5",
+        "error.source_6",
+        "error.source_7",
+    ]
+};
+
+/// The maximum number of sources that can be recorded as distinct fields,
+/// see [`ERROR_SOURCES_INDEXED`].
+pub const MAX_INDEXED_SOURCES: usize = 8;
+
+/// The field name to record whether an error is worth retrying.
+///
+/// Use [`error_retryable`] to populate the field.
+///
+/// It's `exception.retryable` if the `otel` feature is enabled.
+pub const ERROR_RETRYABLE: &str = if cfg!(feature = "otel") {
+    "exception.retryable"
+} else {
+    "error.retryable"
+};
+
+/// The field name to record the [`std::io::ErrorKind`] of an I/O error.
+///
+/// Use [`error_io_kind`] or [`error_io_kind_dyn`] to populate the field.
+///
+/// It's `exception.io_kind` if the `otel` feature is enabled.
+pub const ERROR_IO_KIND: &str = if cfg!(feature = "otel") {
+    "exception.io_kind"
+} else {
+    "error.io_kind"
+};
+
+/// The field name to record the raw OS error code (e.g. `errno` on Unix) of an I/O error.
+///
+/// Use [`error_os_code`] or [`error_os_code_dyn`] to populate the field.
+///
+/// It's `exception.os_code` if the `otel` feature is enabled.
+pub const ERROR_OS_CODE: &str = if cfg!(feature = "otel") {
+    "exception.os_code"
+} else {
+    "error.os_code"
+};
+
+/// The field name to record the name of the enum variant an error was built from.
+///
+/// Use [`error_variant`] to populate the field.
+///
+/// It's `exception.variant` if the `otel` feature is enabled.
+pub const ERROR_VARIANT: &str = if cfg!(feature = "otel") {
+    "exception.variant"
+} else {
+    "error.variant"
+};
+
+/// The field name to record a stable hash of an error, meant to group similar errors
+/// together.
+///
+/// Use [`error_fingerprint`] to populate the field.
+///
+/// It's `exception.fingerprint` if the `otel` feature is enabled.
+pub const ERROR_FINGERPRINT: &str = if cfg!(feature = "otel") {
+    "exception.fingerprint"
+} else {
+    "error.fingerprint"
+};
+
+/// The field name to record the breadcrumbs describing where an error happened,
+/// e.g. `POST /users > tenant=acme`.
+///
+/// Use [`error_context`] to populate the field.
+///
+/// It's `exception.context` if the `otel` feature is enabled.
+pub const ERROR_CONTEXT: &str = if cfg!(feature = "otel") {
+    "exception.context"
+} else {
+    "error.context"
+};
+
+/// The field name to flag a span in which an error occurred.
+///
+/// Use [`mark_span_errored`] to populate the field.
+///
+/// It's `exception.occurred` if the `otel` feature is enabled.
+pub const ERROR_OCCURRED: &str = if cfg!(feature = "otel") {
+    "exception.occurred"
+} else {
+    "error.occurred"
+};
+
+/// The field name to record how long an operation ran before failing, in milliseconds.
+///
+/// Use [`error_duration_ms`] to populate the field.
+///
+/// It's `exception.duration_ms` if the `otel` feature is enabled.
+pub const ERROR_DURATION_MS: &str = if cfg!(feature = "otel") {
+    "exception.duration_ms"
+} else {
+    "error.duration_ms"
+};
+
+/// The field name to record the category of an error, e.g. to route it to the right team.
+///
+/// Use [`error_category`] to populate the field.
+///
+/// It's `exception.category` if the `otel` feature is enabled.
+pub const ERROR_CATEGORY: &str = if cfg!(feature = "otel") {
+    "exception.category"
+} else {
+    "error.category"
+};
+
+/// The field name to record the path of the source file where an error was logged.
+///
+/// Use [`caller_location`] to populate the field.
+pub const CODE_FILEPATH: &str = "code.filepath";
+
+/// The field name to record the line number where an error was logged.
+///
+/// Use [`caller_location`] to populate the field.
+pub const CODE_LINENO: &str = "code.lineno";
+
+/// The field name to record the column number where an error was logged.
+///
+/// Use [`caller_location`] to populate the field.
+pub const CODE_COLUMN: &str = "code.column";
+
+/// The field name to record a batch of errors, logged together via
+/// [`log_errors!`](crate::log_errors).
+///
+/// Use [`error_batch`] to populate the field.
+pub const ERRORS: &str = "errors";
+
+/// The field name to record the number of errors in a batch.
+///
+/// Use [`ErrorBatch::len`] to populate the field.
+pub const ERROR_COUNT: &str = "error_count";
 
 /// The canonical representation for the value in [`ERROR_MESSAGE`].
-pub fn error_message<E: std::fmt::Display>(e: E) -> impl Value {
-    tracing::field::display(e)
+///
+/// The error isn't formatted ahead of time: its `Display` implementation writes straight
+/// into the subscriber's output, without allocating an intermediate `String`.
+/// It only allocates if a redactor is registered, see [`set_redactor`], or if the
+/// `catch-format-panics` feature is enabled.
+/// The same goes for [`error_details`].
+///
+/// It accepts anything that implements `Display`, including the [`core::fmt::Arguments`]
+/// built by `format_args!`: to record a message you're assembling on the spot, skip
+/// `format!` and its `String`:
+///
+/// ```rust
+/// use tracing::{event, Level};
+/// use tracing_log_error::fields;
+///
+/// let peer = "127.0.0.1:8080";
+/// event!(
+///     Level::ERROR,
+///     error.message = fields::error_message(format_args!("Connection to {} reset", peer)),
+/// );
+/// ```
+///
+/// `format_args!` borrows its arguments: the call has to happen within the `event!`
+/// invocation, as above.
+pub fn error_message<E: core::fmt::Display>(e: E) -> impl Value {
+    tracing::field::display(Redacted(e))
+}
+
+/// A variant of [`error_message`] that keeps at most `max_len` characters of the
+/// `Display` representation of the error.
+///
+/// If the representation is longer than that, it's cut short and an ellipsis (`…`) is
+/// appended to signal the truncation.
+/// Lengths are counted in `char`s, so a multi-byte character is never split in half.
+///
+/// ```rust
+/// use tracing_log_error::{fields, log_error};
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Records `error.message = "My e…"`
+/// log_error!(e, { fields::ERROR_MESSAGE } = fields::error_message_truncated(&e, 4));
+/// ```
+pub fn error_message_truncated<E: core::fmt::Display>(e: E, max_len: usize) -> impl Value {
+    truncate(redact(Guarded(e).to_string()), max_len)
+}
+
+fn truncate(mut s: String, max_len: usize) -> String {
+    if let Some((end, _)) = s.char_indices().nth(max_len) {
+        s.truncate(end);
+        s.push('…');
+    }
+    s
 }
 
 /// The canonical representation for the value in [`ERROR_DETAILS`].
-pub fn error_details<E: std::fmt::Debug>(e: E) -> impl Value {
-    tracing::field::debug(e)
+pub fn error_details<E: core::fmt::Debug>(e: E) -> impl Value {
+    tracing::field::debug(Redacted(e))
+}
+
+/// A variant of [`error_details`] that keeps at most `max_len` characters of the
+/// `Debug` representation of the error.
+///
+/// Just like [`error_message_truncated`], the representation is cut short and an
+/// ellipsis (`…`) is appended if it's longer than that.
+/// The outcome can populate [`ERROR_DETAILS_TRUNCATED`] as well, to let consumers know:
+///
+/// ```rust
+/// use tracing_log_error::{fields, log_error};
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let details = fields::error_details_truncated(&e, 6);
+/// // Records `error.details = "Custom…"` and `error.details_truncated = true`
+/// log_error!(
+///     e,
+///     { fields::ERROR_DETAILS } = details.details(),
+///     { fields::ERROR_DETAILS_TRUNCATED } = details.truncated(),
+/// );
+/// ```
+///
+/// Formatting stops as soon as the limit is exceeded, unless a redactor is registered
+/// (see [`set_redactor`]): it needs the full representation.
+pub fn error_details_truncated<E: core::fmt::Debug>(e: E, max_len: usize) -> TruncatedDetails {
+    let (mut details, truncated) = debug_up_to(e, max_len);
+    if truncated {
+        details.push('…');
+    }
+    TruncatedDetails { details, truncated }
+}
+
+/// The details of an error, possibly cut short, see [`error_details_truncated`].
+#[derive(Debug, Clone)]
+pub struct TruncatedDetails {
+    details: String,
+    truncated: bool,
+}
+
+impl TruncatedDetails {
+    /// The value for [`ERROR_DETAILS`].
+    pub fn details(&self) -> &str {
+        &self.details
+    }
+
+    /// The value for [`ERROR_DETAILS_TRUNCATED`].
+    ///
+    /// It's `Some(true)` if the details were cut short, and `None` otherwise: the field
+    /// is only recorded when the details were actually truncated.
+    pub fn truncated(&self) -> Option<bool> {
+        self.truncated.then_some(true)
+    }
+}
+
+/// Used by `log_error!` to compute the details once, and only if the event is enabled,
+/// while recording them in two fields.
+///
+/// Not part of the public API.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct __LazyTruncatedDetails(core::cell::OnceCell<TruncatedDetails>);
+
+impl __LazyTruncatedDetails {
+    pub const fn new() -> Self {
+        Self(core::cell::OnceCell::new())
+    }
+
+    pub fn get(&self, init: impl FnOnce() -> TruncatedDetails) -> &TruncatedDetails {
+        self.0.get_or_init(init)
+    }
+}
+
+/// The first `max_len` characters of the redacted `Debug` representation, and whether
+/// there was more.
+fn debug_up_to<E: core::fmt::Debug>(e: E, max_len: usize) -> (String, bool) {
+    use core::fmt::Write as _;
+
+    /// Gives up, with an error, on the first character past the limit.
+    struct UpTo {
+        s: String,
+        remaining: usize,
+        truncated: bool,
+    }
+
+    impl core::fmt::Write for UpTo {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            for c in s.chars() {
+                if self.remaining == 0 {
+                    self.truncated = true;
+                    return Err(core::fmt::Error);
+                }
+                self.s.push(c);
+                self.remaining -= 1;
+            }
+            Ok(())
+        }
+    }
+
+    let mut up_to = UpTo {
+        s: String::new(),
+        remaining: max_len,
+        truncated: false,
+    };
+    let _ = write!(up_to, "{:?}", Redacted(e));
+    (up_to.s, up_to.truncated)
+}
+
+/// A variant of [`error_details`] that records nothing if the `Debug` representation
+/// of the error is the same as its `Display` representation, ignoring leading and
+/// trailing whitespace.
+///
+/// The details would only repeat what's already in [`ERROR_MESSAGE`].
+pub fn error_details_unless_redundant<E: core::fmt::Display + core::fmt::Debug>(
+    e: E,
+) -> impl Value {
+    _error_details_unless_redundant(e)
+}
+
+fn _error_details_unless_redundant<E: core::fmt::Display + core::fmt::Debug>(
+    e: E,
+) -> Option<String> {
+    let details = format!("{:?}", Guarded(&e));
+    (details.trim() != Guarded(&e).to_string().trim()).then(|| redact(details))
+}
+
+/// A variant of [`error_details`] that uses the alternate `Debug` format (`{:#?}`),
+/// spreading the representation of the error over multiple lines.
+pub fn error_details_pretty<E: core::fmt::Debug>(e: E) -> impl Value {
+    tracing::field::display(Redacted(PrettyDebug(e)))
+}
+
+/// Formats the wrapped value with `{:#?}`, via its `Display` implementation.
+struct PrettyDebug<E>(E);
+
+impl<E: core::fmt::Debug> core::fmt::Display for PrettyDebug<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#?}", self.0)
+    }
 }
 
 /// The canonical representation for the value in [`ERROR_SOURCE_CHAIN`].
-pub fn error_source_chain<E: std::error::Error>(e: E) -> impl Value {
-    _error_source_chain(e)
+///
+/// If the chain loops back on itself, the walk stops at the first repeated source and
+/// a `(cycle detected)` line is appended.
+pub fn error_source_chain<E: core::error::Error>(e: E) -> impl Value {
+    _error_source_chain(e, usize::MAX)
+}
+
+/// A variant of [`error_source_chain`] that stops after `max_depth` sources.
+///
+/// If the chain is longer than `max_depth`, a `... (truncated)` line is appended
+/// to signal that some sources were left out.
+pub fn error_source_chain_capped<E: core::error::Error>(e: E, max_depth: usize) -> impl Value {
+    _error_source_chain(e, max_depth)
+}
+
+/// A variant of [`error_source_chain`] that joins the sources with a custom separator,
+/// e.g. `" -> "` or `"\t"`.
+///
+/// ```rust
+/// use tracing::{event, Level};
+/// use tracing_log_error::fields;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// event!(
+///     Level::ERROR,
+///     error.source_chain = fields::error_source_chain_with_sep(&e, " -> "),
+///     "The connection was dropped"
+/// );
+/// ```
+///
+/// There is no separator before the first source, nor after the last one.
+/// If your backend understands structured values (e.g. JSON), consider
+/// [`error_source_chain_vec`] instead: it keeps sources apart without relying
+/// on a separator that might show up in the messages themselves.
+pub fn error_source_chain_with_sep<E: core::error::Error>(e: E, sep: &str) -> impl Value {
+    _error_source_chain_with_sep(e, sep)
+}
+
+fn _error_source_chain_with_sep<E: core::error::Error>(e: E, sep: &str) -> String {
+    let mut sources = Sources::new(&e);
+    let mut chain: Vec<String> = sources.by_ref().map(|s| Guarded(s).to_string()).collect();
+    if sources.cycle_detected {
+        chain.push(CYCLE_DETECTED.to_owned());
+    }
+    redact(chain.join(sep))
+}
+
+/// Format a source chain you've already collected, the same way [`error_source_chain`]
+/// would.
+///
+/// ```rust
+/// use tracing::{event, Level};
+/// use tracing_log_error::fields;
+///
+/// // E.g. computed at an earlier boundary
+/// let sources = vec!["Connection reset".to_string(), "Broken pipe".to_string()];
+/// event!(
+///     Level::ERROR,
+///     error.source_chain = fields::error_source_chain_from(&sources),
+///     "The connection was dropped"
+/// );
+/// ```
+///
+/// The entries are used as they are: they aren't checked against the actual sources
+/// of the error you're logging.
+pub fn error_source_chain_from<I>(sources: I) -> impl Value
+where
+    I: IntoIterator,
+    I::Item: core::fmt::Display,
+{
+    _error_source_chain_from(sources)
+}
+
+fn _error_source_chain_from<I>(sources: I) -> String
+where
+    I: IntoIterator,
+    I::Item: core::fmt::Display,
+{
+    use core::fmt::Write as _;
+
+    let mut chain = String::new();
+    for s in sources {
+        let _ = writeln!(chain, "- {}", Guarded(s));
+    }
+    redact(chain)
+}
+
+/// A variant of [`error_source_chain`] that collapses consecutive sources with
+/// the same `Display` representation into a single entry.
+///
+/// E.g. three consecutive `connection failed` sources are recorded as
+/// `- connection failed (x3)`.
+pub fn error_source_chain_deduped<E: core::error::Error>(e: E) -> impl Value {
+    _error_source_chain_deduped(e)
+}
+
+fn _error_source_chain_deduped<E: core::error::Error>(e: E) -> String {
+    use core::fmt::Write as _;
+
+    let mut chain = String::new();
+    let mut sources = Sources::new(&e);
+    let mut previous: Option<(String, usize)> = None;
+    for s in sources.by_ref() {
+        let message = Guarded(s).to_string();
+        match &mut previous {
+            Some((previous, count)) if *previous == message => *count += 1,
+            _ => {
+                if let Some((previous, count)) = previous.replace((message, 1)) {
+                    write_deduped(&mut chain, &previous, count);
+                }
+            }
+        }
+    }
+    if let Some((previous, count)) = previous {
+        write_deduped(&mut chain, &previous, count);
+    }
+    if sources.cycle_detected {
+        let _ = writeln!(chain, "{}", CYCLE_DETECTED);
+    }
+    redact(chain)
 }
 
-fn _error_source_chain<E: std::error::Error>(e: E) -> String {
-    use std::fmt::Write as _;
+fn write_deduped(chain: &mut String, message: &str, count: usize) {
+    use core::fmt::Write as _;
+
+    if count == 1 {
+        let _ = writeln!(chain, "- {}", message);
+    } else {
+        let _ = writeln!(chain, "- {} (x{})", message, count);
+    }
+}
+
+/// A variant of [`error_source_chain`] that prefixes each entry with the name of its
+/// type, as reported by [`std::any::type_name`].
+///
+/// ```rust
+/// use tracing::{event, Level};
+/// use tracing_log_error::fields;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Records `- [std::io::error::Error] My error\n`
+/// event!(
+///     Level::ERROR,
+///     error.source_chain = fields::error_source_chain_with_types(&e),
+///     "The connection was dropped"
+/// );
+/// ```
+///
+/// Unlike [`error_source_chain`], the chain starts from the error itself rather than
+/// from its direct source: it's the only level whose concrete type is known.
+/// Sources are returned as `&dyn Error`, so all the following levels are recorded as
+/// `[dyn core::error::Error]`. The same goes for the error itself if you pass a
+/// trait object, e.g. the target of a `Box<dyn Error>`.
+pub fn error_source_chain_with_types<E: core::error::Error + ?Sized>(e: &E) -> impl Value {
+    _error_source_chain_with_types(error_type_name(e), e)
+}
+
+/// Used by `log_error!`, where the type name comes from the expression passed to the
+/// macro rather than from the `dyn Error` it's converted to.
+///
+/// Not part of the public API.
+#[doc(hidden)]
+pub fn __error_source_chain_with_types(
+    type_name: &str,
+    e: &(dyn core::error::Error + '_),
+) -> impl Value {
+    _error_source_chain_with_types(type_name, e)
+}
+
+fn _error_source_chain_with_types<E: core::error::Error + ?Sized>(
+    type_name: &str,
+    e: &E,
+) -> String {
+    use core::fmt::Write as _;
 
     let mut chain = String::new();
-    let mut source = e.source();
-    while let Some(s) = source {
-        let _ = writeln!(chain, "- {}", s);
-        source = s.source();
+    let _ = writeln!(chain, "- [{}] {}", type_name, Guarded(e));
+    let mut sources = Sources::new(e);
+    for s in sources.by_ref() {
+        let _ = writeln!(
+            chain,
+            "- [{}] {}",
+            core::any::type_name_of_val(s),
+            Guarded(s)
+        );
+    }
+    if sources.cycle_detected {
+        let _ = writeln!(chain, "{}", CYCLE_DETECTED);
+    }
+    redact(chain)
+}
+
+/// The canonical representation for the value in [`ERROR_TYPE`].
+///
+/// It returns the name of the type you passed in, as reported by [`std::any::type_name`].
+/// For trait objects (e.g. the target of a `Box<dyn Error>`), you'll get the name of the
+/// trait object type rather than the name of the underlying concrete type.
+pub fn error_type_name<E: ?Sized>(_e: &E) -> &'static str {
+    core::any::type_name::<E>()
+}
+
+/// An alternative representation for the value in [`ERROR_SOURCE_CHAIN`], with
+/// one entry per source.
+///
+/// Each entry is the `Display` representation of the corresponding source,
+/// starting from the direct source of the error.
+///
+/// `Vec<String>` doesn't implement [`Value`], so you'll have to wrap it with
+/// [`tracing::field::debug`] to record it—that's what `log_error!` does when
+/// you pass `source_chain: structured`.
+pub fn error_source_chain_vec<E: core::error::Error>(e: E) -> Vec<String> {
+    let mut sources = Sources::new(&e);
+    let mut chain: Vec<String> = sources
+        .by_ref()
+        .map(|s| redact(Guarded(s).to_string()))
+        .collect();
+    if sources.cycle_detected {
+        chain.push(CYCLE_DETECTED.to_owned());
     }
     chain
 }
+
+/// The canonical representation for the value in [`ERROR_JSON`].
+///
+/// It's a JSON object with the message (see [`error_message`]), the details
+/// (see [`error_details`]) and the chain of sources (see [`error_source_chain_vec`])
+/// of the error:
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// assert_eq!(
+///     fields::error_json(&e),
+///     r#"{"message":"My error","details":"Custom { kind: Other, error: \"My error\" }"}"#
+/// );
+/// ```
+///
+/// The `source_chain` key is omitted if the error has no sources.
+///
+/// The JSON is encoded by this crate: strings are the only values to escape, which
+/// doesn't warrant a dependency on `serde_json`.
+/// Requires the `json` feature.
+#[cfg(feature = "json")]
+pub fn error_json<E: core::error::Error>(e: E) -> String {
+    let mut json = String::from("{\"message\":");
+    write_json_string(&mut json, &redact(Guarded(&e).to_string()));
+    json.push_str(",\"details\":");
+    write_json_string(&mut json, &redact(format!("{:?}", Guarded(&e))));
+    let source_chain = error_source_chain_vec(&e);
+    if !source_chain.is_empty() {
+        json.push_str(",\"source_chain\":[");
+        for (i, source) in source_chain.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write_json_string(&mut json, source);
+        }
+        json.push(']');
+    }
+    json.push('}');
+    json
+}
+
+/// Append `s` to `json` as a JSON string literal.
+#[cfg(feature = "json")]
+fn write_json_string(json: &mut String, s: &str) {
+    use core::fmt::Write as _;
+
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// The canonical representation for the value in [`ERROR_ROOT_CAUSE`].
+///
+/// It's the `Display` representation of the last error in the chain of sources.
+/// If the error has no source, it's the `Display` representation of the error itself.
+pub fn error_root_cause<E: core::error::Error + ?Sized>(e: &E) -> String {
+    redact(match Sources::new(e).last() {
+        Some(root_cause) => Guarded(root_cause).to_string(),
+        None => Guarded(e).to_string(),
+    })
+}
+
+/// The canonical representation for the value in [`ERROR_SUMMARY`].
+///
+/// It's the `Display` representation of the error, up to the first line break (excluded).
+/// It gives log viewers a single-line title for errors with multi-line messages, while
+/// [`ERROR_MESSAGE`] keeps the full message.
+///
+/// ```rust
+/// use tracing::{event, Level};
+/// use tracing_log_error::fields;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "Invalid config\n  at line 3");
+/// // Records `error.summary = "Invalid config"`
+/// event!(Level::ERROR, error.summary = fields::error_summary(&e));
+/// ```
+///
+/// Like [`error_message`], it writes straight into the subscriber's output: formatting
+/// stops as soon as the first line is over.
+pub fn error_summary<E: core::fmt::Display>(e: E) -> impl Value {
+    tracing::field::display(Redacted(FirstLine(e)))
+}
+
+/// Formats the first line of the `Display` representation of the wrapped value.
+struct FirstLine<E>(E);
+
+impl<E: core::fmt::Display> core::fmt::Display for FirstLine<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write;
+
+        /// Forwards everything up to the first line break, then errors out to cut the
+        /// formatting short.
+        struct UntilLineBreak<'a, 'b> {
+            f: &'a mut core::fmt::Formatter<'b>,
+            done: bool,
+        }
+
+        impl Write for UntilLineBreak<'_, '_> {
+            fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                match s.find('\n') {
+                    Some(end) => {
+                        self.f.write_str(s[..end].trim_end_matches('\r'))?;
+                        self.done = true;
+                        Err(core::fmt::Error)
+                    }
+                    None => self.f.write_str(s),
+                }
+            }
+        }
+
+        let mut writer = UntilLineBreak { f, done: false };
+        match write!(writer, "{}", self.0) {
+            Err(_) if writer.done => Ok(()),
+            result => result,
+        }
+    }
+}
+
+/// The canonical representation for the value in [`ERROR_CONTEXT`].
+///
+/// The breadcrumbs are joined with ` > `, from the outermost to the innermost:
+///
+/// ```rust
+/// use tracing::{event, Level};
+/// use tracing_log_error::fields;
+///
+/// // Records `error.context = "POST /users > tenant=acme"`
+/// event!(
+///     Level::ERROR,
+///     error.context = fields::error_context(&["POST /users", "tenant=acme"]),
+///     "The request failed"
+/// );
+/// ```
+///
+/// Nothing is recorded if there are no breadcrumbs.
+pub fn error_context<S: AsRef<str>>(breadcrumbs: &[S]) -> impl Value + '_ {
+    (!breadcrumbs.is_empty()).then(|| tracing::field::display(Breadcrumbs(breadcrumbs)))
+}
+
+/// Joins the wrapped breadcrumbs with ` > `.
+struct Breadcrumbs<'a, S>(&'a [S]);
+
+impl<S: AsRef<str>> core::fmt::Display for Breadcrumbs<'_, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, breadcrumb) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" > ")?;
+            }
+            f.write_str(breadcrumb.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+/// The canonical representation for the value in [`ERROR_VARIANT`].
+///
+/// It's a best-effort guess: the leading identifier of the `Debug` representation of the
+/// error. For enums with a derived `Debug` implementation (e.g. the ones using `thiserror`),
+/// it's the name of the variant:
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// #[derive(Debug)]
+/// enum SignUpError {
+///     InvalidEmail(String),
+///     Timeout { seconds: u64 },
+/// }
+///
+/// let e = SignUpError::Timeout { seconds: 5 };
+/// assert_eq!(fields::error_variant(&e).as_deref(), Some("Timeout"));
+/// ```
+///
+/// For structs with a derived `Debug` implementation, you'll get the name of the struct.
+/// For everything else, the result depends on how `Debug` is implemented: if the
+/// representation doesn't start with an identifier, it returns `None`.
+///
+/// Only the beginning of the representation is formatted: formatting stops as soon as
+/// the identifier is over.
+pub fn error_variant<E: core::fmt::Debug + ?Sized>(e: &E) -> Option<String> {
+    use core::fmt::Write;
+
+    /// Keeps the leading identifier, then errors out to cut the formatting short.
+    struct LeadingIdent(String);
+
+    impl Write for LeadingIdent {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            for c in s.chars() {
+                let is_ident = c == '_' || c.is_alphanumeric();
+                if !is_ident || (self.0.is_empty() && c.is_numeric()) {
+                    return Err(core::fmt::Error);
+                }
+                self.0.push(c);
+            }
+            Ok(())
+        }
+    }
+
+    let mut ident = LeadingIdent(String::new());
+    let _ = write!(ident, "{:?}", e);
+    (!ident.0.is_empty()).then_some(ident.0)
+}
+
+/// The canonical representation for the value in [`ERROR_FINGERPRINT`].
+///
+/// It's a 16-character hex hash of the error type name (see [`error_type_name`]) and of
+/// the root cause message (see [`error_root_cause`]), once normalized.
+/// Two errors of the same type, whose root causes only differ by the ids they embed,
+/// get the same fingerprint:
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// let a = std::io::Error::new(std::io::ErrorKind::Other, "User 42 not found");
+/// let b = std::io::Error::new(std::io::ErrorKind::Other, "User 1337 not found");
+/// assert_eq!(fields::error_fingerprint(&a), fields::error_fingerprint(&b));
+/// ```
+///
+/// The root cause message is normalized via [`normalize_message`], unless you registered
+/// a different normalizer via [`set_fingerprint_normalizer`].
+/// The redactor, if any, is not applied: the message is never recorded as is.
+///
+/// The hash (64-bit FNV-1a) doesn't depend on the platform nor on the Rust version:
+/// fingerprints are stable across deployments, as long as type names and messages are.
+pub fn error_fingerprint<E: core::error::Error + ?Sized>(e: &E) -> String {
+    _error_fingerprint(error_type_name(e), e)
+}
+
+/// Used by `log_error!`, where the type name comes from the expression passed to the
+/// macro rather than from the `dyn Error` it's converted to.
+///
+/// Not part of the public API.
+#[doc(hidden)]
+pub fn __error_fingerprint(type_name: &str, e: &(dyn core::error::Error + '_)) -> String {
+    _error_fingerprint(type_name, e)
+}
+
+fn _error_fingerprint<E: core::error::Error + ?Sized>(type_name: &str, e: &E) -> String {
+    let root_cause = match Sources::new(e).last() {
+        Some(root_cause) => Guarded(root_cause).to_string(),
+        None => Guarded(e).to_string(),
+    };
+    let root_cause = match fingerprint_normalizer() {
+        Some(normalizer) => normalizer(&root_cause),
+        None => normalize_message(&root_cause),
+    };
+    // 64-bit FNV-1a, with a separator that can't show up in a type name.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in type_name.bytes().chain([0]).chain(root_cause.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// The default normalizer for [`error_fingerprint`].
+///
+/// Words made of hex digits and dashes, with at least one digit (e.g. `42`, `0a1b2c`
+/// or a UUID), are replaced by `#`:
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// assert_eq!(
+///     fields::normalize_message("Order 550e8400-e29b-41d4-a716-446655440000 failed after 3 attempts"),
+///     "Order # failed after # attempts"
+/// );
+/// ```
+pub fn normalize_message(message: &str) -> String {
+    let mut normalized = String::with_capacity(message.len());
+    let mut rest = message;
+    while !rest.is_empty() {
+        let word_len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '-'))
+            .unwrap_or(rest.len());
+        if word_len == 0 {
+            let c = rest.chars().next().unwrap();
+            normalized.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let (word, tail) = rest.split_at(word_len);
+        let is_id = word.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+            && word.chars().any(|c| c.is_ascii_digit());
+        normalized.push_str(if is_id { "#" } else { word });
+        rest = tail;
+    }
+    normalized
+}
+
+/// A function to normalize the root cause message before it's hashed by
+/// [`error_fingerprint`], see [`set_fingerprint_normalizer`].
+pub type FingerprintNormalizer = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+#[cfg(feature = "std")]
+static FINGERPRINT_NORMALIZER: OnceLock<FingerprintNormalizer> = OnceLock::new();
+
+/// Replace [`normalize_message`] as the normalizer used by [`error_fingerprint`].
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// // Strip everything after the first colon, it's where our messages keep their details
+/// let normalizer = Box::new(|s: &str| s.split(':').next().unwrap_or_default().to_owned());
+/// assert!(fields::set_fingerprint_normalizer(normalizer).is_ok());
+/// ```
+///
+/// The normalizer can only be set once: subsequent calls return the normalizer you
+/// passed in as an error.
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn set_fingerprint_normalizer(
+    normalizer: FingerprintNormalizer,
+) -> Result<(), FingerprintNormalizer> {
+    FINGERPRINT_NORMALIZER.set(normalizer)
+}
+
+fn fingerprint_normalizer() -> Option<&'static FingerprintNormalizer> {
+    #[cfg(feature = "std")]
+    return FINGERPRINT_NORMALIZER.get();
+    #[cfg(not(feature = "std"))]
+    None
+}
+
+/// The canonical representation for the value in [`ERRORS`].
+///
+/// Each error is captured as its message (see [`error_message`]) and its details
+/// (see [`error_details`]).
+///
+/// [`ErrorBatch`] doesn't implement [`Value`], so you'll have to wrap it with
+/// [`tracing::field::debug`] to record it—that's what `log_errors!` does.
+pub fn error_batch<I>(errors: I) -> ErrorBatch
+where
+    I: IntoIterator,
+    I::Item: core::error::Error,
+{
+    ErrorBatch {
+        entries: errors
+            .into_iter()
+            .map(|e| {
+                (
+                    redact(Guarded(&e).to_string()),
+                    redact(format!("{:?}", Guarded(&e))),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// A batch of errors, see [`error_batch`].
+///
+/// Its `Debug` representation is a list with one `{"message": .., "details": ..}`
+/// entry per error.
+pub struct ErrorBatch {
+    entries: Vec<(String, String)>,
+}
+
+impl ErrorBatch {
+    /// The number of errors in the batch.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no errors in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl core::fmt::Debug for ErrorBatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        struct Entry<'a>(&'a str, &'a str);
+
+        impl core::fmt::Debug for Entry<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_map()
+                    .entry(&"message", &self.0)
+                    .entry(&"details", &self.1)
+                    .finish()
+            }
+        }
+
+        f.debug_list()
+            .entries(
+                self.entries
+                    .iter()
+                    .map(|(message, details)| Entry(message, details)),
+            )
+            .finish()
+    }
+}
+
+/// The canonical representation for the value in [`ERROR_CHAIN_DEPTH`].
+///
+/// It's the number of [`source`](std::error::Error::source) hops from the error to its
+/// root cause: an error without a source has a depth of 0.
+/// If the chain loops back on itself, each source is only counted once.
+pub fn error_chain_depth<E: core::error::Error + ?Sized>(e: &E) -> u32 {
+    u32::try_from(Sources::new(e).count()).unwrap_or(u32::MAX)
+}
+
+/// The canonical representation for the value in [`ERROR_DURATION_MS`].
+///
+/// It's the number of whole milliseconds in the duration: sub-millisecond precision
+/// is truncated, durations that don't fit in a `u64` saturate to `u64::MAX`.
+pub fn error_duration_ms(took: core::time::Duration) -> u64 {
+    u64::try_from(took.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// The canonical representation for the value in [`ERROR_CATEGORY`].
+///
+/// It's the `Display` representation of the category.
+/// The crate doesn't define any category: bring your own, typically a fieldless enum.
+pub fn error_category<C: core::fmt::Display>(category: C) -> impl Value {
+    tracing::field::display(category)
+}
+
+/// The canonical representation for the values in [`ERROR_SOURCES_INDEXED`].
+///
+/// It yields the `Display` representation of up to `max` sources, starting from the
+/// error's direct source.
+/// Sources beyond `max` are left out, with no marker.
+/// If the chain loops back on itself, the walk stops at the first repeated source.
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // `e` has no sources
+/// assert_eq!(fields::error_sources_indexed(&e, 3).count(), 0);
+/// ```
+pub fn error_sources_indexed<E: core::error::Error + ?Sized>(
+    e: &E,
+    max: usize,
+) -> impl Iterator<Item = String> + '_ {
+    Sources::new(e)
+        .take(max)
+        .map(|s| redact(Guarded(s).to_string()))
+}
+
+/// The canonical representation for the value in [`ERROR_RETRYABLE`], as
+/// reported by [`ErrorClassification::is_retryable`].
+///
+/// Nothing is recorded in the `None` case.
+///
+/// [`ErrorClassification::is_retryable`]: crate::ErrorClassification::is_retryable
+pub fn error_retryable<E: crate::ErrorClassification + ?Sized>(e: &E) -> Option<bool> {
+    e.is_retryable()
+}
+
+pub(crate) fn _error_source_chain<E: core::error::Error>(e: E, max_depth: usize) -> String {
+    use core::fmt::Write as _;
+
+    let mut chain = String::new();
+    let mut sources = Sources::new(&e);
+    for (depth, s) in sources.by_ref().enumerate() {
+        if depth == max_depth {
+            chain.push_str("... (truncated)\n");
+            return redact(chain);
+        }
+        let _ = writeln!(chain, "- {}", Guarded(s));
+    }
+    if sources.cycle_detected {
+        let _ = writeln!(chain, "{}", CYCLE_DETECTED);
+    }
+    redact(chain)
+}
+
+const CYCLE_DETECTED: &str = "(cycle detected)";
+
+/// Render a human-readable report for an error, e.g. to print it to `stderr` in a CLI.
+///
+/// The report starts with the message of the error, followed by its sources, one per
+/// line:
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// eprintln!("Error: {}", fields::render_report(&e));
+/// ```
+///
+/// For an error with two sources, the report looks like this:
+///
+/// ```text
+/// Failed to load the configuration
+///
+/// Caused by:
+///     0: Failed to read `config.toml`
+///     1: Permission denied (os error 13)
+/// ```
+///
+/// If the `nightly` feature is enabled and the error provides its own
+/// [`Backtrace`] via `Error::provide`, it's appended under a
+/// `Backtrace:` header.
+/// No backtrace is captured on the spot: it would point at the code rendering the report,
+/// rather than at the one that failed.
+///
+/// It uses the same representations as the fields recorded by
+/// [`log_error!`](crate::log_error): the registered redactor, if any, is applied, and
+/// cycles in the chain of sources are detected.
+pub fn render_report<E: core::error::Error + ?Sized>(e: &E) -> String {
+    use core::fmt::Write as _;
+
+    let mut report = Guarded(e).to_string();
+    let mut sources = Sources::new(e);
+    for (i, source) in sources.by_ref().enumerate() {
+        if i == 0 {
+            report.push_str("\n\nCaused by:");
+        }
+        let _ = write!(report, "\n    {}: {}", i, Guarded(source));
+    }
+    if sources.cycle_detected {
+        let _ = write!(report, "\n    {}", CYCLE_DETECTED);
+    }
+    #[cfg(feature = "nightly")]
+    if let Some(backtrace) = core::error::request_ref::<Backtrace>(e).and_then(render_backtrace) {
+        let _ = write!(report, "\n\nBacktrace:\n{}", backtrace.trim_end());
+    }
+    redact(report)
+}
+
+/// A message body that carries the error fields with it, for consumers that only look at
+/// the message of an event.
+///
+/// That's typically the case for `log` records, e.g. when a dependency only consumes the
+/// `log` crate and events are forwarded to it via `tracing`'s `log` feature: the
+/// structured fields get flattened, or dropped entirely, by most `log` loggers.
+///
+/// ```rust
+/// use tracing_log_error::{fields, log_error};
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let message = fields::render_log_message("The connection was dropped", &e);
+/// assert_eq!(message, "The connection was dropped: My error");
+/// // The fields are still recorded, for `tracing` subscribers
+/// log_error!(e, "{}", message);
+/// ```
+///
+/// It's the message, followed by the report for the error (see [`render_report`]):
+/// its sources, one per line, and its backtrace, if the `nightly` feature is enabled.
+pub fn render_log_message<M, E>(message: M, e: &E) -> String
+where
+    M: core::fmt::Display,
+    E: core::error::Error + ?Sized,
+{
+    format!("{}: {}", message, render_report(e))
+}
+
+/// A function to scrub sensitive data from the representation of an error,
+/// see [`set_redactor`].
+pub type Redactor = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+#[cfg(feature = "std")]
+static REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+/// Register a function to scrub sensitive data from error fields before they're recorded.
+///
+/// The redactor is applied to the values computed by [`error_message`], [`error_details`],
+/// [`error_details_pretty`], [`error_root_cause`], [`error_summary`] and to all the representations
+/// of the source chain (e.g. [`error_source_chain`]).
+/// It receives the full representation of the field and returns the one to be recorded.
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// let redactor = Box::new(|s: &str| s.replace("hunter2", "[REDACTED]"));
+/// assert!(fields::set_redactor(redactor).is_ok());
+/// ```
+///
+/// The redactor can only be set once: subsequent calls return the redactor you passed in
+/// as an error.
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn set_redactor(redactor: Redactor) -> Result<(), Redactor> {
+    REDACTOR.set(redactor)
+}
+
+fn redactor() -> Option<&'static Redactor> {
+    #[cfg(feature = "std")]
+    return REDACTOR.get();
+    #[cfg(not(feature = "std"))]
+    None
+}
+
+/// Applies the registered redactor, if any, then escapes control characters.
+fn redact(s: String) -> String {
+    match redactor() {
+        Some(redactor) => escape_control(redactor(&s)),
+        None => escape_control(s),
+    }
+}
+
+/// Escapes the control characters in the given string, except for line breaks and tabs.
+fn escape_control(s: String) -> String {
+    use core::fmt::Write as _;
+
+    if !s.chars().any(is_escaped) {
+        return s;
+    }
+    let mut escaped = String::with_capacity(s.len());
+    let _ = EscapeControl(&mut escaped).write_str(&s);
+    escaped
+}
+
+fn is_escaped(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+/// Forwards everything to the wrapped writer, escaping control characters on the way.
+struct EscapeControl<W>(W);
+
+impl<W: core::fmt::Write> core::fmt::Write for EscapeControl<W> {
+    fn write_str(&mut self, mut s: &str) -> core::fmt::Result {
+        while let Some((i, c)) = s.char_indices().find(|(_, c)| is_escaped(*c)) {
+            self.0.write_str(&s[..i])?;
+            write!(self.0, "{}", c.escape_default())?;
+            s = &s[i + c.len_utf8()..];
+        }
+        self.0.write_str(s)
+    }
+}
+
+/// Applies the registered redactor, if any, to the `Display` and `Debug`
+/// representations of the wrapped value, then escapes control characters.
+pub(crate) struct Redacted<T>(pub(crate) T);
+
+impl<T: core::fmt::Display> core::fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write as _;
+
+        match redactor() {
+            Some(_) => f.write_str(&redact(Guarded(&self.0).to_string())),
+            None => write!(EscapeControl(f), "{}", Guarded(&self.0)),
+        }
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write as _;
+
+        match redactor() {
+            Some(_) if f.alternate() => f.write_str(&redact(format!("{:#?}", Guarded(&self.0)))),
+            Some(_) => f.write_str(&redact(format!("{:?}", Guarded(&self.0)))),
+            None if f.alternate() => write!(EscapeControl(f), "{:#?}", Guarded(&self.0)),
+            None => write!(EscapeControl(f), "{:?}", Guarded(&self.0)),
+        }
+    }
+}
+
+/// The placeholder recorded instead of the representation of an error whose `Display`
+/// or `Debug` implementation panicked.
+///
+/// Requires the `catch-format-panics` feature.
+#[cfg(feature = "catch-format-panics")]
+pub const PANICKED_WHILE_FORMATTING: &str = "<panicked while formatting>";
+
+/// Formats the wrapped value, falling back to [`PANICKED_WHILE_FORMATTING`] if its
+/// `Display` or `Debug` implementation panics.
+///
+/// It's transparent unless the `catch-format-panics` feature is enabled.
+struct Guarded<T>(T);
+
+impl<T: core::fmt::Display> core::fmt::Display for Guarded<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        #[cfg(feature = "catch-format-panics")]
+        return f.write_str(&catch_format_panics(|| self.0.to_string()));
+        #[cfg(not(feature = "catch-format-panics"))]
+        self.0.fmt(f)
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Guarded<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        #[cfg(feature = "catch-format-panics")]
+        return f.write_str(&catch_format_panics(|| {
+            if f.alternate() {
+                format!("{:#?}", self.0)
+            } else {
+                format!("{:?}", self.0)
+            }
+        }));
+        #[cfg(not(feature = "catch-format-panics"))]
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "catch-format-panics")]
+fn catch_format_panics(format: impl FnOnce() -> String) -> String {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(format))
+        .unwrap_or_else(|_| PANICKED_WHILE_FORMATTING.to_owned())
+}
+
+/// Iterate over the chain of sources of an error, to build your own representation of it.
+///
+/// ```rust
+/// use tracing_log_error::fields;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let chain: Vec<String> = fields::sources(&e).map(|s| s.to_string()).collect();
+/// assert!(chain.is_empty());
+/// ```
+///
+/// The error itself is not included, only its sources.
+/// It's the same walk used to compute [`error_source_chain`] and its variants, with the same
+/// protection against cycles: check out [`Sources`] for more details.
+pub fn sources<E: core::error::Error + ?Sized>(e: &E) -> Sources<'_> {
+    Sources::new(e)
+}
+
+/// The number of visited sources that [`Sources`] keeps track of without allocating.
+const INLINE_VISITED: usize = 8;
+
+/// An iterator over the chain of sources of an error, see [`sources`].
+///
+/// It stops early if it encounters a source it has already visited, to avoid looping
+/// forever on errors that (directly or indirectly) return themselves as their own source.
+/// Use [`Sources::cycle_detected`] to find out if that happened.
+///
+/// Keeping track of the visited sources doesn't allocate, unless the chain is longer
+/// than 8 sources.
+#[derive(Debug)]
+pub struct Sources<'a> {
+    next: Option<&'a (dyn core::error::Error + 'static)>,
+    inline_visited: [Option<&'a (dyn core::error::Error + 'static)>; INLINE_VISITED],
+    n_inline_visited: usize,
+    spilled_visited: Vec<&'a (dyn core::error::Error + 'static)>,
+    cycle_detected: bool,
+}
+
+impl<'a> Sources<'a> {
+    fn new<E: core::error::Error + ?Sized>(e: &'a E) -> Self {
+        Self {
+            next: e.source(),
+            inline_visited: [None; INLINE_VISITED],
+            n_inline_visited: 0,
+            spilled_visited: Vec::new(),
+            cycle_detected: false,
+        }
+    }
+
+    /// `true` if the iterator stopped early because the chain of sources loops back
+    /// onto itself.
+    pub fn cycle_detected(&self) -> bool {
+        self.cycle_detected
+    }
+
+    /// Record `source` as visited. It returns `false` if it had already been visited.
+    fn visit(&mut self, source: &'a (dyn core::error::Error + 'static)) -> bool {
+        let inline = self.inline_visited[..self.n_inline_visited]
+            .iter()
+            .flatten();
+        if inline
+            .chain(&self.spilled_visited)
+            .any(|visited| core::ptr::eq(*visited, source))
+        {
+            return false;
+        }
+        if self.n_inline_visited < INLINE_VISITED {
+            self.inline_visited[self.n_inline_visited] = Some(source);
+            self.n_inline_visited += 1;
+        } else {
+            self.spilled_visited.push(source);
+        }
+        true
+    }
+}
+
+impl<'a> Iterator for Sources<'a> {
+    type Item = &'a (dyn core::error::Error + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source = self.next.take()?;
+        // A source can share its address with the error that wraps it (e.g. a
+        // newtype, or a zero-sized error), so the vtable is compared too.
+        if !self.visit(source) {
+            self.cycle_detected = true;
+            return None;
+        }
+        self.next = source.source();
+        Some(source)
+    }
+}
+
+impl core::iter::FusedIterator for Sources<'_> {}
+
+/// The canonical representation for the value in [`ERROR_IO_KIND`].
+///
+/// It's the `Debug` representation of the [`std::io::ErrorKind`], e.g. `NotFound` or
+/// `PermissionDenied`.
+///
+/// ```rust
+/// use tracing_log_error::{fields, log_error};
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::NotFound, "My error");
+/// log_error!(e, { fields::ERROR_IO_KIND } = fields::error_io_kind(&e), "Failed to open the file");
+/// ```
+#[cfg(feature = "std")]
+pub fn error_io_kind(e: &std::io::Error) -> impl Value {
+    tracing::field::debug(e.kind())
+}
+
+/// A best-effort variant of [`error_io_kind`], for when you don't know the concrete
+/// type of the error.
+///
+/// It returns `None` if the error isn't a [`std::io::Error`].
+/// The returned value can be recorded as is—nothing is recorded in the `None` case.
+#[cfg(feature = "std")]
+pub fn error_io_kind_dyn(e: &(dyn core::error::Error + 'static)) -> Option<impl Value> {
+    e.downcast_ref::<std::io::Error>().map(error_io_kind)
+}
+
+/// The canonical representation for the value in [`ERROR_OS_CODE`], as reported
+/// by [`std::io::Error::raw_os_error`].
+///
+/// It returns `None` if the error didn't originate from the operating system.
+/// Nothing is recorded in the `None` case.
+#[cfg(feature = "std")]
+pub fn error_os_code(e: &std::io::Error) -> Option<i32> {
+    e.raw_os_error()
+}
+
+/// A best-effort variant of [`error_os_code`], for when you don't know the concrete
+/// type of the error.
+///
+/// It returns `None` if the error isn't a [`std::io::Error`], or if it didn't originate
+/// from the operating system.
+#[cfg(feature = "std")]
+pub fn error_os_code_dyn(e: &(dyn core::error::Error + 'static)) -> Option<i32> {
+    e.downcast_ref::<std::io::Error>().and_then(error_os_code)
+}
+
+/// Record the error fields on the current span, rather than on a new event.
+///
+/// It records [`ERROR_MESSAGE`], [`ERROR_DETAILS`], [`ERROR_SOURCE_CHAIN`] and
+/// [`ERROR_TYPE`], using the same representations as [`log_error!`](crate::log_error).
+///
+/// `tracing` only records fields that were declared when the span was created:
+/// the span must declare the error fields as [`Empty`](tracing::field::Empty) upfront,
+/// otherwise they're silently dropped.
+///
+/// ```rust
+/// use tracing::field::Empty;
+/// use tracing_log_error::fields;
+///
+/// let span = tracing::info_span!(
+///     "request",
+///     { fields::ERROR_MESSAGE } = Empty,
+///     { fields::ERROR_DETAILS } = Empty,
+///     { fields::ERROR_SOURCE_CHAIN } = Empty,
+///     { fields::ERROR_TYPE } = Empty,
+/// );
+/// let _guard = span.enter();
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// fields::record_on_current_span(&e);
+/// ```
+///
+/// [`error_span!`](crate::error_span) declares them for you.
+pub fn record_on_current_span<E: core::error::Error + ?Sized>(e: &E) {
+    let span = tracing::Span::current();
+    span.record(ERROR_MESSAGE, error_message(e));
+    span.record(ERROR_DETAILS, error_details(e));
+    span.record(ERROR_SOURCE_CHAIN, error_source_chain(e));
+    span.record(ERROR_TYPE, error_type_name(e));
+}
+
+/// Flag the current span as errored: it records [`ERROR_OCCURRED`] as `true`, as well as
+/// [`ERROR_MESSAGE`].
+///
+/// It's a lighter alternative to [`record_on_current_span`] when you only need to know
+/// that something went wrong in a scope, e.g. to find the spans of a trace that
+/// contain errors.
+/// As with [`record_on_current_span`], the span must declare the fields as
+/// [`Empty`](tracing::field::Empty) upfront, otherwise they're silently dropped:
+///
+/// ```rust
+/// use tracing::field::Empty;
+/// use tracing_log_error::fields;
+///
+/// let span = tracing::info_span!(
+///     "request",
+///     { fields::ERROR_OCCURRED } = Empty,
+///     { fields::ERROR_MESSAGE } = Empty,
+/// );
+/// let _guard = span.enter();
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// fields::mark_span_errored(&e);
+///
+/// // Events emitted from here on, including the ones in child spans, are
+/// // reported within a span that carries `error.occurred = true`.
+/// tracing::info!("Cleaning up");
+/// ```
+///
+/// `tracing` doesn't copy the fields of a span onto its children: the flag is only
+/// visible to child spans and events through their parent.
+/// Most subscribers do show it—e.g. `tracing-subscriber`'s `fmt` layer prints the fields
+/// of every span in the current scope, and OpenTelemetry exporters keep the span
+/// hierarchy.
+/// [`error_span!`](crate::error_span) declares both fields for you.
+pub fn mark_span_errored<E: core::error::Error + ?Sized>(e: &E) {
+    let span = tracing::Span::current();
+    span.record(ERROR_OCCURRED, true);
+    span.record(ERROR_MESSAGE, error_message(e));
+}
+
+/// The location of the code that invoked this function, as reported by
+/// [`std::panic::Location::caller`].
+///
+/// Use [`Location::file`](std::panic::Location::file),
+/// [`Location::line`](std::panic::Location::line) and
+/// [`Location::column`](std::panic::Location::column) to populate
+/// [`CODE_FILEPATH`], [`CODE_LINENO`] and [`CODE_COLUMN`] respectively.
+///
+/// It's a `#[track_caller]` function: when invoked from the expansion of a macro,
+/// such as `log_error!`, it returns the location of the macro invocation.
+#[track_caller]
+pub fn caller_location() -> &'static core::panic::Location<'static> {
+    core::panic::Location::caller()
+}
+
+/// Retrieve a value of type `T` provided by the error, or by one of its sources,
+/// via `Error::provide`.
+///
+/// ```rust
+/// #![feature(error_generic_member_access)]
+/// use std::error::{Error, Request};
+/// use tracing_log_error::fields;
+///
+/// #[derive(Debug)]
+/// struct RequestId(u64);
+///
+/// #[derive(Debug)]
+/// struct Timeout(RequestId);
+///
+/// impl std::fmt::Display for Timeout {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str("The request timed out")
+///     }
+/// }
+///
+/// impl Error for Timeout {
+///     fn provide<'a>(&'a self, request: &mut Request<'a>) {
+///         request.provide_ref::<RequestId>(&self.0);
+///     }
+/// }
+///
+/// let e = Timeout(RequestId(42));
+/// assert_eq!(fields::error_provided::<RequestId, _>(&e).map(|id| id.0), Some(42));
+/// ```
+///
+/// The error itself is asked first, then each of its sources, starting from the
+/// direct one: the first value found wins.
+/// Requires the `nightly` feature, which relies on the unstable
+/// `error_generic_member_access` API.
+#[cfg(feature = "nightly")]
+pub fn error_provided<T, E>(e: &E) -> Option<&T>
+where
+    T: ?Sized + 'static,
+    E: core::error::Error + ?Sized,
+{
+    core::error::request_ref::<T>(e)
+        .or_else(|| Sources::new(e).find_map(|s| core::error::request_ref::<T>(s)))
+}
+
+/// The canonical representation for the value in [`ERROR_BACKTRACE`].
+///
+/// If the `nightly` feature is enabled and the error provides its own
+/// [`Backtrace`] via `Error::provide`, that backtrace is used.
+/// Otherwise, a fresh backtrace is captured with [`Backtrace::capture`].
+///
+/// Nothing is recorded if backtraces are disabled or unsupported.
+#[cfg(feature = "std")]
+pub fn error_backtrace<E: core::error::Error>(e: E) -> impl Value {
+    _error_backtrace(e)
+}
+
+/// The value in [`ERROR_BACKTRACE`] when the `otel` feature is enabled.
+///
+/// OpenTelemetry has a single `exception.stacktrace` field: it holds the captured
+/// backtrace (see [`error_backtrace`]) if there is one, and falls back
+/// to the chain of sources (see [`error_source_chain`]) otherwise.
+#[cfg(feature = "std")]
+pub fn error_backtrace_or_source_chain<E: core::error::Error>(e: E) -> impl Value {
+    match _error_backtrace(&e) {
+        Some(backtrace) => backtrace,
+        None => _error_source_chain(&e, usize::MAX),
+    }
+}
+
+/// The value in [`ERROR_BACKTRACE`] for an `anyhow::Error`: the backtrace
+/// that `anyhow` captured when the error was created.
+///
+/// It's `None` if no backtrace was captured—e.g. if neither `RUST_BACKTRACE` nor
+/// `RUST_LIB_BACKTRACE` were set.
+#[cfg(feature = "anyhow")]
+pub fn anyhow_backtrace(e: &anyhow::Error) -> Option<String> {
+    render_backtrace(e.backtrace())
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn _error_backtrace<E: core::error::Error>(e: E) -> Option<String> {
+    #[cfg(feature = "nightly")]
+    if let Some(backtrace) = core::error::request_ref::<Backtrace>(&e) {
+        return render_backtrace(backtrace);
+    }
+    #[cfg(not(feature = "nightly"))]
+    let _ = e;
+    render_backtrace(&Backtrace::capture())
+}
+
+#[cfg(feature = "std")]
+fn render_backtrace(backtrace: &Backtrace) -> Option<String> {
+    match backtrace.status() {
+        BacktraceStatus::Captured => Some(backtrace.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Chained {
+        message: &'static str,
+        source: Option<Box<Chained>>,
+    }
+
+    impl std::fmt::Display for Chained {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.message)
+        }
+    }
+
+    impl std::error::Error for Chained {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.source.as_deref().map(|s| s as _)
+        }
+    }
+
+    fn chain(messages: &[&'static str]) -> Chained {
+        let (message, rest) = messages.split_first().unwrap();
+        Chained {
+            message,
+            source: (!rest.is_empty()).then(|| Box::new(chain(rest))),
+        }
+    }
+
+    #[test]
+    fn source_chain() {
+        let e = chain(&["top", "middle", "bottom"]);
+        assert_eq!(_error_source_chain(&e, usize::MAX), "- middle\n- bottom\n");
+        assert_eq!(error_source_chain_vec(&e), vec!["middle", "bottom"]);
+    }
+
+    #[derive(Debug)]
+    struct Ouroboros;
+
+    impl std::fmt::Display for Ouroboros {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("ouroboros")
+        }
+    }
+
+    impl std::error::Error for Ouroboros {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(self)
+        }
+    }
+
+    #[test]
+    fn cyclic_source_chain() {
+        assert_eq!(
+            _error_source_chain(Ouroboros, usize::MAX),
+            "- ouroboros\n(cycle detected)\n"
+        );
+        assert_eq!(
+            error_source_chain_vec(Ouroboros),
+            vec!["ouroboros", "(cycle detected)"]
+        );
+        assert_eq!(
+            _error_source_chain(Ouroboros, 3),
+            "- ouroboros\n(cycle detected)\n"
+        );
+    }
+
+    #[test]
+    fn sources_iterator() {
+        let e = chain(&["top", "middle", "bottom"]);
+        let mut iter = sources(&e);
+        let messages: Vec<String> = iter.by_ref().map(|s| s.to_string()).collect();
+        assert_eq!(messages, vec!["middle", "bottom"]);
+        assert!(!iter.cycle_detected());
+        assert!(iter.next().is_none());
+
+        /// A ring of errors, longer than the number of sources tracked inline.
+        #[derive(Debug)]
+        struct Ring(usize);
+
+        static RING: [Ring; 12] = [
+            Ring(0),
+            Ring(1),
+            Ring(2),
+            Ring(3),
+            Ring(4),
+            Ring(5),
+            Ring(6),
+            Ring(7),
+            Ring(8),
+            Ring(9),
+            Ring(10),
+            Ring(11),
+        ];
+
+        impl std::fmt::Display for Ring {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "ring {}", self.0)
+            }
+        }
+
+        impl std::error::Error for Ring {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&RING[(self.0 + 1) % RING.len()])
+            }
+        }
+
+        let mut iter = sources(&RING[0]);
+        assert_eq!(iter.by_ref().count(), RING.len());
+        assert!(iter.cycle_detected());
+    }
+
+    #[test]
+    fn root_cause() {
+        assert_eq!(
+            error_root_cause(&chain(&["top", "middle", "bottom"])),
+            "bottom"
+        );
+        assert_eq!(error_root_cause(&chain(&["top"])), "top");
+        assert_eq!(error_root_cause(&Ouroboros), "ouroboros");
+    }
+
+    #[test]
+    fn nested_newtypes() {
+        // Each error is stored by value at offset 0 of its parent: they all share the
+        // same address.
+        #[derive(Debug)]
+        struct Outer(Middle);
+
+        #[derive(Debug)]
+        struct Middle(std::io::Error);
+
+        impl std::fmt::Display for Outer {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("outer")
+            }
+        }
+
+        impl std::fmt::Display for Middle {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("middle")
+            }
+        }
+
+        impl std::error::Error for Outer {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        impl std::error::Error for Middle {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let e = Outer(Middle(std::io::Error::other("leaf")));
+        let mut sources = Sources::new(&e);
+        let messages: Vec<String> = sources.by_ref().map(|s| s.to_string()).collect();
+        assert_eq!(messages, ["middle", "leaf"]);
+        assert!(!sources.cycle_detected());
+        assert_eq!(error_chain_depth(&e), 2);
+        assert_eq!(error_root_cause(&e), "leaf");
+        assert_eq!(
+            render_report(&e),
+            "outer\n\nCaused by:\n    0: middle\n    1: leaf"
+        );
+    }
+
+    #[test]
+    fn chain_depth() {
+        assert_eq!(error_chain_depth(&chain(&["top", "middle", "bottom"])), 2);
+        assert_eq!(error_chain_depth(&chain(&["top"])), 0);
+        assert_eq!(error_chain_depth(&Ouroboros), 1);
+    }
+
+    #[test]
+    fn io_kind() {
+        let e = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "My error");
+        let events = crate::testing::with_captured(|| {
+            tracing::error!(
+                { ERROR_IO_KIND } = error_io_kind(&e),
+                dyn_kind = error_io_kind_dyn(&e),
+                not_io = error_io_kind_dyn(&Ouroboros),
+            );
+        });
+        let fields = &events[0].1;
+        assert_eq!(fields[ERROR_IO_KIND], "ConnectionReset");
+        assert_eq!(fields["dyn_kind"], "ConnectionReset");
+        assert!(!fields.contains_key("not_io"));
+    }
+
+    #[test]
+    fn os_code() {
+        let e = std::io::Error::from_raw_os_error(2);
+        assert_eq!(error_os_code(&e), Some(2));
+        assert_eq!(error_os_code_dyn(&e), Some(2));
+        assert_eq!(error_os_code(&std::io::Error::other("My error")), None);
+        assert_eq!(error_os_code_dyn(&Ouroboros), None);
+    }
+
+    #[test]
+    fn deduped_source_chain() {
+        let e = chain(&["top", "foo", "foo", "foo", "bar", "foo"]);
+        assert_eq!(
+            _error_source_chain_deduped(&e),
+            "- foo (x3)\n- bar\n- foo\n"
+        );
+        assert_eq!(_error_source_chain_deduped(chain(&["top"])), "");
+        assert_eq!(
+            _error_source_chain_deduped(Ouroboros),
+            "- ouroboros\n(cycle detected)\n"
+        );
+    }
+
+    #[test]
+    fn source_chain_with_types() {
+        let e = chain(&["top", "middle", "bottom"]);
+        assert_eq!(
+            _error_source_chain_with_types(error_type_name(&e), &e),
+            "- [tracing_log_error::fields::tests::Chained] top\n\
+             - [dyn core::error::Error] middle\n\
+             - [dyn core::error::Error] bottom\n"
+        );
+        assert_eq!(
+            _error_source_chain_with_types(error_type_name(&Ouroboros), &Ouroboros),
+            "- [tracing_log_error::fields::tests::Ouroboros] ouroboros\n\
+             - [dyn core::error::Error] ouroboros\n\
+             (cycle detected)\n"
+        );
+    }
+
+    #[test]
+    fn source_chain_with_sep() {
+        let e = chain(&["top", "middle", "bottom"]);
+        assert_eq!(_error_source_chain_with_sep(&e, " -> "), "middle -> bottom");
+        assert_eq!(_error_source_chain_with_sep(&e, "\n"), "middle\nbottom");
+        assert_eq!(_error_source_chain_with_sep(chain(&["top"]), "\t"), "");
+        assert_eq!(
+            _error_source_chain_with_sep(Ouroboros, " -> "),
+            "ouroboros -> (cycle detected)"
+        );
+    }
+
+    #[test]
+    fn sources_indexed() {
+        let e = chain(&["top", "middle", "bottom"]);
+        assert_eq!(
+            error_sources_indexed(&e, 3).collect::<Vec<_>>(),
+            ["middle", "bottom"]
+        );
+        assert_eq!(error_sources_indexed(&e, 1).collect::<Vec<_>>(), ["middle"]);
+        assert_eq!(error_sources_indexed(&e, 0).count(), 0);
+        assert_eq!(
+            error_sources_indexed(&Ouroboros, 3).collect::<Vec<_>>(),
+            ["ouroboros"]
+        );
+    }
+
+    /// A subscriber that keeps track of the values recorded on its only span.
+    #[derive(Default)]
+    struct SingleSpan {
+        span: std::sync::Mutex<Option<&'static tracing::Metadata<'static>>>,
+        entered: std::sync::Mutex<bool>,
+        fields: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<&'static str, String>>>,
+    }
+
+    impl tracing::Subscriber for SingleSpan {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            *self.span.lock().unwrap() = Some(span.metadata());
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            values.record(&mut crate::testing::FieldVisitor(
+                &mut self.fields.lock().unwrap(),
+            ));
+        }
+
+        fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+        fn event(&self, _event: &tracing::Event<'_>) {}
+
+        fn enter(&self, _span: &tracing::span::Id) {
+            *self.entered.lock().unwrap() = true;
+        }
+
+        fn exit(&self, _span: &tracing::span::Id) {
+            *self.entered.lock().unwrap() = false;
+        }
+
+        fn current_span(&self) -> tracing_core::span::Current {
+            match *self.span.lock().unwrap() {
+                Some(metadata) if *self.entered.lock().unwrap() => {
+                    tracing_core::span::Current::new(tracing::span::Id::from_u64(1), metadata)
+                }
+                _ => tracing_core::span::Current::none(),
+            }
+        }
+    }
+
+    #[test]
+    fn records_on_current_span() {
+        let subscriber = SingleSpan::default();
+        let fields = subscriber.fields.clone();
+        let e = chain(&["top", "bottom"]);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "request",
+                { ERROR_MESSAGE } = tracing::field::Empty,
+                { ERROR_DETAILS } = tracing::field::Empty,
+                { ERROR_SOURCE_CHAIN } = tracing::field::Empty,
+            );
+            let _guard = span.enter();
+            record_on_current_span(&e);
+        });
+        let fields = fields.lock().unwrap();
+        assert_eq!(fields[ERROR_MESSAGE], "top");
+        assert_eq!(fields[ERROR_DETAILS], format!("{:?}", e));
+        assert_eq!(fields[ERROR_SOURCE_CHAIN], "- bottom\n");
+        // Not declared on the span
+        assert!(!fields.contains_key(ERROR_TYPE));
+    }
+
+    #[test]
+    fn marks_span_errored() {
+        let subscriber = SingleSpan::default();
+        let fields = subscriber.fields.clone();
+        let e = chain(&["top", "bottom"]);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = crate::error_span!(tracing::Level::INFO, "request");
+            let _guard = span.enter();
+            mark_span_errored(&e);
+        });
+        let fields = fields.lock().unwrap();
+        assert_eq!(fields[ERROR_OCCURRED], "true");
+        assert_eq!(fields[ERROR_MESSAGE], "top");
+        // Not recorded by `mark_span_errored`
+        assert!(!fields.contains_key(ERROR_DETAILS));
+    }
+
+    #[test]
+    fn message_from_arguments() {
+        let events = crate::testing::with_captured(|| {
+            tracing::error!(
+                { ERROR_MESSAGE } = error_message(format_args!("Connection to {} reset", "peer")),
+            );
+        });
+        assert_eq!(events[0].1[ERROR_MESSAGE], "Connection to peer reset");
+    }
+
+    #[test]
+    fn truncated_details() {
+        assert_eq!(debug_up_to("My error", 3), ("\"My".to_owned(), true));
+        assert_eq!(
+            debug_up_to("My error", 10),
+            ("\"My error\"".to_owned(), false)
+        );
+        assert_eq!(debug_up_to("Café", 5), ("\"Café".to_owned(), true));
+        assert_eq!(debug_up_to("", 0), (String::new(), true));
+        assert_eq!(debug_up_to((), 2), ("()".to_owned(), false));
+        let details = error_details_truncated("My error", 3);
+        assert_eq!(details.details(), "\"My…");
+        assert_eq!(details.truncated(), Some(true));
+        let details = error_details_truncated("My error", 10);
+        assert_eq!(details.details(), "\"My error\"");
+        assert_eq!(details.truncated(), None);
+    }
+
+    #[test]
+    fn truncated_message() {
+        assert_eq!(truncate("My error".to_owned(), 4), "My e…");
+        assert_eq!(truncate("My error".to_owned(), 8), "My error");
+        assert_eq!(truncate("My error".to_owned(), 0), "…");
+        // `é` and `—` take more than one byte
+        assert_eq!(truncate("Café — closed".to_owned(), 6), "Café —…");
+        assert_eq!(truncate("Café".to_owned(), 3), "Caf…");
+    }
+
+    #[test]
+    fn source_chain_from() {
+        let e = chain(&["top", "middle", "bottom"]);
+        let sources = vec!["middle".to_string(), "bottom".to_string()];
+        assert_eq!(
+            _error_source_chain_from(&sources),
+            _error_source_chain(&e, usize::MAX)
+        );
+        assert_eq!(_error_source_chain_from(Vec::<String>::new()), "");
+    }
+
+    #[test]
+    fn fingerprint() {
+        assert_eq!(
+            normalize_message("User 42 (id: 0a1b-2c) not found, retried 3x"),
+            "User # (id: #) not found, retried 3x"
+        );
+        assert_eq!(
+            normalize_message("dead beef, ipv4, ünïcode 7"),
+            "dead beef, ipv4, ünïcode #"
+        );
+
+        let a = chain(&["top", "Timed out after 30s"]);
+        let b = chain(&["other top", "Timed out after 30s"]);
+        let c = chain(&["top", "Connection refused"]);
+        let fingerprint = _error_fingerprint("a::Error", &a);
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(fingerprint, _error_fingerprint("a::Error", &b));
+        assert_ne!(fingerprint, _error_fingerprint("a::Error", &c));
+        assert_ne!(fingerprint, _error_fingerprint("b::Error", &a));
+        // The hash is stable: it mustn't change across releases
+        assert_eq!(
+            _error_fingerprint("a::Error", &std::io::Error::other("x")),
+            "bda5a1284464f956"
+        );
+    }
+
+    #[test]
+    fn duration_ms() {
+        use core::time::Duration;
+
+        assert_eq!(error_duration_ms(Duration::ZERO), 0);
+        assert_eq!(error_duration_ms(Duration::from_micros(999)), 0);
+        assert_eq!(error_duration_ms(Duration::from_millis(1500)), 1500);
+        assert_eq!(error_duration_ms(Duration::from_secs(30)), 30_000);
+        assert_eq!(error_duration_ms(Duration::new(5, 999_999_999)), 5999);
+        assert_eq!(error_duration_ms(Duration::MAX), u64::MAX);
+    }
+
+    #[test]
+    #[cfg(feature = "catch-format-panics")]
+    fn format_panics() {
+        #[derive(Debug)]
+        struct Panicking;
+
+        impl std::fmt::Display for Panicking {
+            fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                panic!("Oops")
+            }
+        }
+
+        impl std::error::Error for Panicking {}
+
+        #[derive(Debug)]
+        struct Wrapper(Panicking);
+
+        impl std::fmt::Display for Wrapper {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "Wrapper")
+            }
+        }
+
+        impl std::error::Error for Wrapper {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let e = Wrapper(Panicking);
+        let events = crate::testing::with_captured(|| {
+            tracing::error!(
+                { ERROR_MESSAGE } = error_message(&e.0),
+                { ERROR_DETAILS } = error_details(&e.0),
+                { ERROR_SOURCE_CHAIN } = error_source_chain(&e),
+                { ERROR_ROOT_CAUSE } = error_root_cause(&e),
+            );
+        });
+        let fields = &events[0].1;
+        assert_eq!(fields[ERROR_MESSAGE], PANICKED_WHILE_FORMATTING);
+        assert_eq!(fields[ERROR_DETAILS], "Panicking");
+        assert_eq!(
+            fields[ERROR_SOURCE_CHAIN],
+            format!("- {}\n", PANICKED_WHILE_FORMATTING)
+        );
+        assert_eq!(fields[ERROR_ROOT_CAUSE], PANICKED_WHILE_FORMATTING);
+
+        #[cfg(feature = "json")]
+        assert_eq!(
+            error_json(&e.0),
+            format!(
+                r#"{{"message":"{}","details":"Panicking"}}"#,
+                PANICKED_WHILE_FORMATTING
+            )
+        );
+        // The root cause is fingerprinted as the placeholder
+        assert_eq!(error_fingerprint(&e).len(), 16);
+        assert_eq!(
+            format!("{:?}", error_batch([Panicking])),
+            format!(
+                r#"[{{"message": "{}", "details": "Panicking"}}]"#,
+                PANICKED_WHILE_FORMATTING
+            )
+        );
+    }
+
+    #[test]
+    fn control_characters() {
+        let e = std::io::Error::other("\u{1b}[31mRed\u{1b}[0m\r\n\tand\0 more");
+        let events = crate::testing::with_captured(|| {
+            tracing::error!(
+                { ERROR_MESSAGE } = error_message(&e),
+                { ERROR_DETAILS } = error_details(&e),
+                { ERROR_SOURCE_CHAIN } = error_source_chain(chain(&["top", "\u{7}bell"])),
+            );
+        });
+        let fields = &events[0].1;
+        assert_eq!(
+            fields[ERROR_MESSAGE],
+            "\\u{1b}[31mRed\\u{1b}[0m\\r\n\tand\\u{0} more"
+        );
+        assert!(!fields[ERROR_DETAILS].chars().any(is_escaped));
+        assert_eq!(fields[ERROR_SOURCE_CHAIN], "- \\u{7}bell\n");
+        assert_eq!(escape_control("Clean\n".to_owned()), "Clean\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8_paths() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::Path;
+
+        let path = Path::new(OsStr::from_bytes(b"/tmp/\xFFconfig\x1b.toml"));
+        let e = std::io::Error::other(format!("Can't read {}", path.display()));
+        let with_debug = std::io::Error::other(format!("Can't read {:?}", path));
+        let events = crate::testing::with_captured(|| {
+            for e in [&e, &with_debug] {
+                tracing::error!(
+                    { ERROR_MESSAGE } = error_message(e),
+                    { ERROR_DETAILS } = error_details(e),
+                    { ERROR_SOURCE_CHAIN } = error_source_chain(e),
+                );
+            }
+        });
+        for (_, fields) in &events {
+            for field in [ERROR_MESSAGE, ERROR_DETAILS, ERROR_SOURCE_CHAIN] {
+                assert!(!fields[field].chars().any(is_escaped));
+            }
+        }
+        // Lossy: invalid bytes are replaced with `U+FFFD`
+        assert_eq!(
+            events[0].1[ERROR_MESSAGE],
+            "Can't read /tmp/\u{FFFD}config\\u{1b}.toml"
+        );
+        // `Debug` escapes invalid bytes on its own
+        assert_eq!(
+            events[1].1[ERROR_MESSAGE],
+            "Can't read \"/tmp/\\xFFconfig\\u{1b}.toml\""
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let events = crate::testing::with_captured(|| {
+            for message in [
+                "One line",
+                "First\nSecond\nThird",
+                "Windows\r\nStyle",
+                "\nEmpty",
+            ] {
+                tracing::error!({ ERROR_SUMMARY } = error_summary(message));
+            }
+        });
+        let summaries: Vec<&str> = events
+            .iter()
+            .map(|(_, fields)| fields[ERROR_SUMMARY].as_str())
+            .collect();
+        assert_eq!(summaries, ["One line", "First", "Windows", ""]);
+    }
+
+    #[test]
+    fn variant() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        enum MyError {
+            Unit,
+            Tuple(u64),
+            Struct { id: u64 },
+        }
+
+        assert_eq!(error_variant(&MyError::Unit).as_deref(), Some("Unit"));
+        assert_eq!(error_variant(&MyError::Tuple(1)).as_deref(), Some("Tuple"));
+        assert_eq!(
+            error_variant(&MyError::Struct { id: 1 }).as_deref(),
+            Some("Struct")
+        );
+        assert_eq!(error_variant("Not an identifier"), None);
+        assert_eq!(error_variant(&42), None);
+        assert_eq!(
+            error_variant(&std::io::Error::other("My error")).as_deref(),
+            Some("Custom")
+        );
+    }
+
+    #[test]
+    fn redundant_details() {
+        #[derive(Debug)]
+        struct Timeout;
+
+        impl std::fmt::Display for Timeout {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(" Timeout\n")
+            }
+        }
+
+        assert_eq!(_error_details_unless_redundant(Timeout), None);
+        let e = chain(&["top"]);
+        assert_eq!(
+            _error_details_unless_redundant(&e),
+            Some(format!("{:?}", e))
+        );
+    }
+
+    #[test]
+    fn report() {
+        assert_eq!(render_report(&chain(&["top"])), "top");
+        assert_eq!(
+            render_report(&chain(&["top", "middle", "bottom"])),
+            "top\n\nCaused by:\n    0: middle\n    1: bottom"
+        );
+        assert_eq!(
+            render_report(&Ouroboros),
+            "ouroboros\n\nCaused by:\n    0: ouroboros\n    (cycle detected)"
+        );
+        assert_eq!(
+            render_log_message("Yay", &chain(&["top", "bottom"])),
+            "Yay: top\n\nCaused by:\n    0: bottom"
+        );
+    }
+
+    #[test]
+    fn capped_source_chain() {
+        let e = chain(&["top", "middle", "bottom"]);
+        assert_eq!(_error_source_chain(&e, 1), "- middle\n... (truncated)\n");
+        assert_eq!(_error_source_chain(&e, 2), "- middle\n- bottom\n");
+        assert_eq!(_error_source_chain(&e, 0), "... (truncated)\n");
+    }
+
+    #[test]
+    fn error_batch_representation() {
+        let errors = [chain(&["first"]), chain(&["second", "cause"])];
+        let batch = error_batch(&errors);
+        assert_eq!(batch.len(), 2);
+        assert_eq!(
+            format!("{:?}", batch),
+            format!(
+                r#"[{{"message": "first", "details": {:?}}}, {{"message": "second", "details": {:?}}}]"#,
+                format!("{:?}", errors[0]),
+                format!("{:?}", errors[1]),
+            )
+        );
+        assert!(error_batch(Vec::<Chained>::new()).is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let json = error_json(chain(&["top", "middle \"quoted\"", "bottom"]));
+        assert!(json.starts_with(r#"{"message":"top","details":"Chained {"#));
+        assert!(json.ends_with(r#","source_chain":["middle \"quoted\"","bottom"]}"#));
+        assert!(!error_json(chain(&["top"])).contains("source_chain"));
+
+        let mut s = String::new();
+        write_json_string(&mut s, "a\\b\n\u{1}");
+        assert_eq!(s, r#""a\\b\n\u0001""#);
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn provided() {
+        #[derive(Debug, PartialEq)]
+        struct RequestId(u64);
+
+        #[derive(Debug)]
+        struct Providing {
+            id: Option<RequestId>,
+            source: Option<Box<Providing>>,
+        }
+
+        impl std::fmt::Display for Providing {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("Providing")
+            }
+        }
+
+        impl std::error::Error for Providing {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                self.source.as_deref().map(|e| e as _)
+            }
+
+            fn provide<'a>(&'a self, request: &mut std::error::Request<'a>) {
+                if let Some(id) = &self.id {
+                    request.provide_ref::<RequestId>(id);
+                }
+            }
+        }
+
+        let providing = |id: Option<u64>, source: Option<Providing>| Providing {
+            id: id.map(RequestId),
+            source: source.map(Box::new),
+        };
+        let e = providing(Some(1), Some(providing(Some(2), None)));
+        assert_eq!(error_provided::<RequestId, _>(&e), Some(&RequestId(1)));
+        // Provided by a source
+        let e = providing(None, Some(providing(None, Some(providing(Some(3), None)))));
+        assert_eq!(error_provided::<RequestId, _>(&e), Some(&RequestId(3)));
+        let e = providing(None, Some(providing(None, None)));
+        assert_eq!(error_provided::<RequestId, _>(&e), None);
+        assert_eq!(error_provided::<u64, _>(&e), None);
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn anyhow_captured_backtrace() {
+        let e = anyhow::anyhow!("Hey");
+        let expected = match e.backtrace().status() {
+            BacktraceStatus::Captured => Some(e.backtrace().to_string()),
+            _ => None,
+        };
+        assert_eq!(anyhow_backtrace(&e), expected);
+    }
+}
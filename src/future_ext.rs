@@ -0,0 +1,179 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use tracing::Level;
+
+use crate::ResultExt;
+
+/// An extension trait to log errors returned by fallible futures.
+///
+/// ```rust
+/// use tracing_log_error::FutureExt;
+///
+/// async fn fetch() -> Result<String, std::io::Error> {
+///     // [...]
+///     # Ok(String::new())
+/// }
+///
+/// async fn handler() -> Result<(), std::io::Error> {
+///     let body = fetch().log_err("Failed to fetch the body").await?;
+///     // [...]
+///     # let _ = body;
+///     Ok(())
+/// }
+/// ```
+///
+/// The error is logged via [`ResultExt`], as soon as the future resolves to an `Err`.
+/// The event is emitted while the future is being polled: it's recorded under the span
+/// that's current at that point (e.g. the span attached via `tracing::Instrument`), rather
+/// than the one that was current when the future was created.
+pub trait FutureExt<T, E>: Future<Output = Result<T, E>> + Sized {
+    /// Log the error, if the future resolves to one, at the `ERROR` level.
+    /// The output of the future is returned unchanged.
+    fn log_err(self, msg: &str) -> LogErr<'_, Self> {
+        self.log_err_at(Level::ERROR, msg)
+    }
+
+    /// Log the error, if the future resolves to one, at the specified level.
+    /// The output of the future is returned unchanged.
+    fn log_err_at(self, level: Level, msg: &str) -> LogErr<'_, Self> {
+        LogErr {
+            future: self,
+            level,
+            msg,
+        }
+    }
+}
+
+impl<T, E: core::error::Error, F: Future<Output = Result<T, E>>> FutureExt<T, E> for F {}
+
+pin_project_lite::pin_project! {
+    /// The future returned by [`FutureExt::log_err`] and [`FutureExt::log_err_at`].
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    #[derive(Debug)]
+    pub struct LogErr<'a, F> {
+        #[pin]
+        future: F,
+        level: Level,
+        msg: &'a str,
+    }
+}
+
+impl<T, E: core::error::Error, F: Future<Output = Result<T, E>>> Future for LogErr<'_, F> {
+    type Output = Result<T, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        this.future
+            .poll(cx)
+            .map(|output| output.log_err_at(*this.level, this.msg))
+    }
+}
+
+#[cfg(all(test, not(all(feature = "disable-in-release", not(debug_assertions)))))]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Wake, Waker};
+
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    thread_local! {
+        static CURRENT_SPAN: Cell<Option<u64>> = const { Cell::new(None) };
+    }
+
+    /// A subscriber that keeps track of the span that was current when each event
+    /// was emitted.
+    #[derive(Default)]
+    struct SpanTracker {
+        spans: Arc<Mutex<Vec<Option<u64>>>>,
+        next_span_id: AtomicU64,
+    }
+
+    impl Subscriber for SpanTracker {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(self.next_span_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {
+            self.spans.lock().unwrap().push(CURRENT_SPAN.get());
+        }
+
+        fn enter(&self, span: &Id) {
+            CURRENT_SPAN.set(Some(span.into_u64()));
+        }
+
+        fn exit(&self, _span: &Id) {
+            CURRENT_SPAN.set(None);
+        }
+    }
+
+    /// A future that is pending on its first poll (unless `.1` is `true`), then resolves to `.0`.
+    struct YieldOnce<T>(Option<T>, bool);
+
+    impl<T: Unpin> Future for YieldOnce<T> {
+        type Output = T;
+
+        fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<T> {
+            if !self.1 {
+                self.1 = true;
+                return Poll::Pending;
+            }
+            Poll::Ready(self.0.take().unwrap())
+        }
+    }
+
+    /// A waker that does nothing: the test polls the future by hand.
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn logs_under_the_span_current_at_poll_time() {
+        let subscriber = SpanTracker::default();
+        let spans = subscriber.spans.clone();
+        tracing::subscriber::with_default(subscriber, || {
+            let created = tracing::info_span!("created");
+            let polled = tracing::info_span!("polled");
+            let mut future = {
+                let _guard = created.enter();
+                Box::pin(
+                    YieldOnce(Some(Err::<(), _>(std::io::Error::other("My error"))), false)
+                        .log_err_at(Level::WARN, "Failed"),
+                )
+            };
+            let waker = Waker::from(Arc::new(NoopWaker));
+            let mut cx = Context::from_waker(&waker);
+            let _guard = polled.enter();
+            assert!(future.as_mut().poll(&mut cx).is_pending());
+            assert!(spans.lock().unwrap().is_empty());
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(Err(e)) => assert_eq!(e.to_string(), "My error"),
+                _ => panic!("The future should have resolved to an error"),
+            }
+            assert_eq!(
+                *spans.lock().unwrap(),
+                [polled.id().map(|id| id.into_u64())]
+            );
+
+            let mut future =
+                Box::pin(YieldOnce(Some(Ok::<_, std::io::Error>(())), true).log_err("Failed"));
+            assert!(matches!(future.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+            assert_eq!(spans.lock().unwrap().len(), 1);
+        });
+    }
+}
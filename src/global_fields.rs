@@ -0,0 +1,44 @@
+use std::sync::RwLock;
+
+static GLOBAL_FIELDS: RwLock<Vec<(String, String)>> = RwLock::new(Vec::new());
+
+/// Register a field to be added to every event emitted by [`LoggableError`](crate::LoggableError),
+/// e.g. the name or the version of your service.
+///
+/// ```rust
+/// use tracing_log_error::{register_global_field, LoggableError};
+///
+/// register_global_field("service.name", "checkout");
+/// register_global_field("service.version", env!("CARGO_PKG_VERSION"));
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Records `service.name` and `service.version` 👇
+/// LoggableError::new(&e).emit("The request failed");
+/// ```
+///
+/// Registering a field with the same key again replaces its value.
+/// Fields added to an event via [`LoggableError::field`](crate::LoggableError::field) take
+/// precedence over global fields with the same key.
+///
+/// `log_error!` doesn't record global fields: the set of fields of its events is fixed
+/// at compile-time. If you're using `tracing-subscriber`, consider recording them on
+/// a root span instead.
+pub fn register_global_field(key: impl Into<String>, value: impl Into<String>) {
+    let (key, value) = (key.into(), value.into());
+    let mut fields = GLOBAL_FIELDS.write().unwrap_or_else(|e| e.into_inner());
+    match fields.iter_mut().find(|(k, _)| *k == key) {
+        Some(entry) => entry.1 = value,
+        None => fields.push((key, value)),
+    }
+}
+
+/// A snapshot of the registered global fields.
+///
+/// The lock is released before returning, so that subscribers are free to register
+/// global fields while the event is being emitted.
+pub(crate) fn global_fields() -> Vec<(String, String)> {
+    GLOBAL_FIELDS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
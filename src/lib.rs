@@ -15,6 +15,10 @@
 //! - The `Display` representation of the error, in the `error.message` field.
 //! - The `Debug` representation of the error, in the `error.details` field.
 //! - The chain of error sources, in the `error.source_chain` field.
+//! - The Rust type name of the error, in the `error.type` field.
+//! - Whether the error is worth retrying, in the `error.retryable` field, if its type
+//!   implements [`ErrorClassification`].
+//! - The raw OS error code of I/O errors, in the `error.os_code` field, if there is one.
 //!
 //! Using raw `tracing`, the equivalent would be:
 //!
@@ -28,6 +32,7 @@
 //!     error.message = fields::error_message(&e),
 //!     error.details = fields::error_details(&e),
 //!     error.source_chain = fields::error_source_chain(&e),
+//!     error.type = fields::error_type_name(&e),
 //!     "The connection was dropped"
 //! );
 //! ```
@@ -44,9 +49,17 @@
 //!
 //! ## Some errors don't implement the `Error` trait
 //!
-//! Some common error reporting types, like `anyhow::Error` or `eyre::Report`
-//! or `Box<dyn std::error::Error>`, don't implement the `Error` trait.
-//! If you try to use `log_error!` with them directly, you'll get a compiler error.
+//! Some common error reporting types, like `anyhow::Error` or `eyre::Report`,
+//! don't implement the `Error` trait.
+//! If you try to use `log_error!` with them directly, you'll get a compiler error—the
+//! same one you get for anything else that isn't an error:
+//!
+//! ```rust,compile_fail,E0277
+//! use tracing_log_error::log_error;
+//!
+//! // error[E0277]: `&str` doesn't implement `std::error::Error`
+//! log_error!("Not an error", "An error occurred");
+//! ```
 //!
 //! Good news: you can still use `log_error!` with them!
 //! They dereference to a type that implements the `Error` trait, so you can
@@ -61,11 +74,180 @@
 //! log_error!(*e, "An error occurred");
 //! ```
 //!
+//! Boxed errors don't need the `*` either: pass the box, or a reference to it, and
+//! `log_error!` borrows the error inside—the box is still yours afterwards:
+//!
+//! ```rust
+//! use tracing_log_error::log_error;
+//!
+//! let e: Box<dyn std::error::Error + Send + Sync> = "Hey".into();
+//! log_error!(e, "An error occurred");
+//! log_error!(&e, "An error occurred");
+//! // `as_ref` works too, and so does any `&dyn Error`
+//! log_error!(e.as_ref(), "An error occurred");
+//! ```
+//!
+//! For `anyhow::Error`, you can skip the `*` by enabling the `anyhow` feature.
+//! The source chain is the one tracked by `anyhow`, including the context
+//! you attached along the way:
+//!
+//! ```rust
+//! # #[cfg(feature = "anyhow")]
+//! # {
+//! use tracing_log_error::log_error;
+//! use anyhow::{anyhow, Context};
+//!
+//! let e = Err::<(), _>(anyhow!("Hey")).context("Something went wrong").unwrap_err();
+//! log_error!(e, "An error occurred");
+//! log_error!(&e, "An error occurred");
+//! # }
+//! ```
+//!
 //! ## Advanced usage
 //!
 //! Check out [`log_error!`](crate::log_error)'s documentation for more examples and details.
 //! You can customize the log level, add custom fields, and more.
+//!
+//! If you'd rather log errors inline, as they propagate through `?`, check out [`ResultExt`]
+//! or [`log_map_err!`].
+//! For futures that resolve to a `Result`, check out [`FutureExt`].
+//!
+//! For unrecoverable errors, [`log_error_panic!`] logs them before panicking.
+//! To keep the expensive diagnostics behind a `TRACE` filter, check out [`log_error_tiered!`].
+//!
+//! If the custom fields are only known at runtime, check out [`LoggableError`].
+//!
+//! If you need the error fields for something else too (e.g. an HTTP response), check out
+//! [`ErrorReport`].
+//! To print an error in a CLI, consistently with what gets logged, check out
+//! [`fields::render_report`].
+//! If your events end up in `log` records, which tend to drop the fields, check out
+//! [`fields::render_log_message`].
+//!
+//! If the same error keeps firing in a hot loop, check out [`log_error_throttled!`].
+//! If the same error gets logged at several boundaries, check out [`LoggedOnce`].
+//! To log an error only if it wasn't handled, check out [`LogErrorOnDrop`].
+//! To log to a subscriber other than the default one, check out [`log_error_to!`].
+//! For cross-cutting concerns (e.g. counting errors), check out [`set_on_error`].
+//!
+//! To attach the error fields to the current span, rather than to a new event, check out
+//! [`fields::record_on_current_span`] and [`error_span!`].
+//!
+//! To check which fields were recorded by `log_error!` in your own tests, enable the `testing`
+//! feature and check out the `testing` module.
+//!
+//! ## `no_std`
+//!
+//! `tracing_log_error` works in `no_std` environments, as long as `alloc` is available.
+//! Disable the default `std` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! tracing-log-error = { version = "0.1", default-features = false }
+//! ```
+//!
+//! Errors are then expected to implement [`core::error::Error`].
+//! Some functionality requires `std` and isn't available: backtraces (`backtrace: true`),
+//! I/O-specific fields (e.g. `error.os_code`), redaction, custom fingerprint normalizers,
+//! `LoggableError`, `LogErrorOnDrop`, `PoisonedLock`, `set_on_error`, `log_error_throttled!`,
+//! `log_error_tiered!`, `log_error_to!` and `log_poison!`.
+//!
+//! ## Stripping error logs from release builds
+//!
+//! Enable the `disable-in-release` feature to compile `log_error!` invocations down to
+//! nothing in release builds.
+//! The exact condition is `all(feature = "disable-in-release", not(debug_assertions))`,
+//! evaluated when compiling `tracing_log_error`: debug assertions are off in the default
+//! `release` profile, unless you turn them on via `debug-assertions = true`.
+//!
+//! The arguments are still type-checked, so you won't get warnings about unused variables,
+//! but they're never evaluated—error expression included.
+//! It applies to `log_error!` and to everything built on top of it (e.g. `log_failure!`,
+//! `log_error_throttled!` or [`ResultExt`]), but not to [`LoggableError`],
+//! [`ErrorReport::log`] or `log_errors!`.
+//! `log_error_return!`, `log_error_panic!` and `log_poison!` still evaluate their first
+//! argument, since they hand it back or panic with it, but they don't log it.
+//!
+//! To only strip the less severe levels, rely on `tracing`'s static level filters instead.
+//! Enable one of the `max_level_off`, `max_level_error`, `max_level_warn`, `max_level_info`,
+//! `max_level_debug` or `max_level_trace` features of the `tracing` crate (or their
+//! `release_max_level_*` counterparts, which only apply when debug assertions are disabled):
+//!
+//! ```toml
+//! [dependencies]
+//! tracing = { version = "0.1", features = ["release_max_level_info"] }
+//! ```
+//!
+//! `log_error!` expands to `tracing::event!`, which checks the level of the event against
+//! the static maximum before doing anything else: the fields of a `DEBUG` invocation are
+//! never computed, and the hook registered via `set_on_error` isn't invoked.
+//! If the level is only known at runtime (e.g. `level: level_from_config(&s)`, or no
+//! `level:` at all with the `runtime-default-level` feature), the level expression is still evaluated,
+//! but the branches for the disabled levels are compiled out.
+//! Without static filters, such an invocation expands to five callsites, one per level:
+//! pass `level: Level::ERROR` (or any other path to a level) to get a single one.
+//!
+//! ## OpenTelemetry
+//!
+//! Enable the `otel` feature to use the field names from the
+//! [OpenTelemetry semantic conventions for exceptions](https://opentelemetry.io/docs/specs/semconv/exceptions/exceptions-spans/)
+//! (e.g. `exception.message` instead of `error.message`), without touching your
+//! `log_error!` invocations.
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "nightly", feature(error_generic_member_access))]
+extern crate alloc;
+
+#[doc(hidden)]
+pub mod as_error;
+mod classification;
+mod default_level;
+#[cfg(feature = "std")]
+mod dynamic;
+mod error_report;
 pub mod fields;
+mod future_ext;
+#[cfg(feature = "std")]
+mod global_fields;
+#[cfg(feature = "std")]
+mod log_on_drop;
+#[cfg(feature = "std")]
+mod loggable_error;
+mod logged_once;
+#[cfg(feature = "std")]
+mod on_error;
+#[cfg(feature = "std")]
+mod poisoned_lock;
+mod result_ext;
+mod status;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "std")]
+mod throttle;
+
+pub use classification::ErrorClassification;
+pub use default_level::level_from_config;
+#[cfg(feature = "runtime-default-level")]
+pub use default_level::{default_level, set_default_level};
+pub use error_report::ErrorReport;
+pub use future_ext::{FutureExt, LogErr};
+#[cfg(feature = "std")]
+pub use global_fields::register_global_field;
+#[cfg(feature = "std")]
+pub use log_on_drop::LogErrorOnDrop;
+#[cfg(feature = "std")]
+pub use loggable_error::LoggableError;
+pub use logged_once::LoggedOnce;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use on_error::{__has_on_error, __on_error};
+#[cfg(feature = "std")]
+pub use on_error::{set_on_error, OnError};
+#[cfg(feature = "std")]
+pub use poisoned_lock::PoisonedLock;
+pub use result_ext::ResultExt;
+pub use status::level_for_status;
+#[cfg(feature = "std")]
+pub use throttle::Throttle;
 
 /// A macro that desugars to an invocation of `tracing::event!` with all
 /// error-related fields (the ones in [the `fields` module](crate::fields))
@@ -86,10 +268,33 @@ pub mod fields;
 ///     error.message = fields::error_message(&e),
 ///     error.details = fields::error_details(&e),
 ///     error.source_chain = fields::error_source_chain(&e),
+///     error.type = fields::error_type_name(&e),
 ///     "The connection was dropped"
 /// );
 /// ```
 ///
+/// The error fields are computed in the field position of `tracing::event!`, which only
+/// evaluates them if the event is enabled: `log_error!` doesn't invoke the `Display` or
+/// `Debug` implementations of the error, nor walk its sources, if no subscriber is interested
+/// in the event.
+///
+/// # Message first
+///
+/// If you'd rather read the message before the error, put the message first and separate
+/// it from the error with a `;`:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let peer = "127.0.0.1:8080";
+/// // These two invocations are equivalent 👇
+/// log_error!("The connection to {} was dropped", peer; e, level: tracing::Level::WARN);
+/// log_error!(e, level: tracing::Level::WARN, "The connection to {} was dropped", peer);
+/// ```
+///
+/// The error can be followed by any of the arguments documented below, except the message.
+///
 /// # Custom fields
 ///
 /// You can add custom fields to the log event by prepending them ahead of the
@@ -109,10 +314,25 @@ pub mod fields;
 ///     error.message = fields::error_message(&e),
 ///     error.details = fields::error_details(&e),
 ///     error.source_chain = fields::error_source_chain(&e),
+///     error.type = fields::error_type_name(&e),
 ///     "The connection was dropped"
 /// );
 /// ```
 ///
+/// Custom field expressions end up in the field position of `tracing::event!`, just like
+/// the error fields: they're only evaluated if the event is enabled, and so are the
+/// arguments of the message.
+/// There's no need to defer expensive fields behind a closure:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// # fn expensive_lookup() -> u64 { 42 }
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // `expensive_lookup` is only called if someone is interested in the event 👇
+/// log_error!(e, tenant_id = expensive_lookup(), "The connection was dropped");
+/// ```
+///
 /// # Custom level
 ///
 /// It may be useful, in some cases, to log an error at a level other than
@@ -131,117 +351,2289 @@ pub mod fields;
 ///     error.message = fields::error_message(&e),
 ///     error.details = fields::error_details(&e),
 ///     error.source_chain = fields::error_source_chain(&e),
+///     error.type = fields::error_type_name(&e),
 ///     "The connection was dropped"
 /// );
 /// ```
 ///
-#[macro_export]
-macro_rules! log_error {
-    // ...
-    ($err:expr, level: $lvl:expr, { $($fields:tt)* }) => (
-        ::tracing::event!(
-            $lvl,
-            {{ $crate::fields::ERROR_MESSAGE }} = $crate::fields::error_message(&$err),
-            {{ $crate::fields::ERROR_DETAILS }} = $crate::fields::error_details(&$err),
-            {{ $crate::fields::ERROR_SOURCE_CHAIN }} = $crate::fields::error_source_chain(&$err),
-            $($fields)*
+/// The level can also be chosen at runtime:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let transient = e.kind() == std::io::ErrorKind::ConnectionReset;
+/// log_error!(e, level: if transient { Level::WARN } else { Level::ERROR }, "The connection was dropped");
+/// ```
+///
+/// `tracing` needs to know the level of each event at compile-time, so `log_error!`
+/// treats the two cases differently:
+///
+/// - A path to a level (e.g. `Level::WARN` or `tracing::Level::WARN`) expands to a
+///   single event.
+/// - Any other expression (e.g. a variable, a function call or an `if`) is evaluated
+///   once, then matched against each of the five levels: there is one event per level
+///   in the expanded code, but only the matching one is emitted.
+///
+/// To pick the level from an HTTP status code, check out [`level_for_status`].
+///
+/// If you don't specify a level, `log_error!` uses `ERROR`.
+/// With the `runtime-default-level` feature, it uses the global default level instead,
+/// which you can change via `set_default_level`: it's read at runtime, so it costs a
+/// relaxed atomic load per invocation and, in the expanded code, one event per level,
+/// as for any other runtime level.
+///
+/// # Custom target
+///
+/// By default, the event target is the module path of the `log_error!` invocation.
+/// You can override it with the `target:` argument, just like with `tracing::event!`:
+///
+/// ```rust
+/// use tracing::{event, Level};
+/// use tracing_log_error::{fields, log_error};
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // This 👇
+/// log_error!(e, target: "db::pool", level: Level::WARN, "The connection was dropped");
+/// // is equivalent to this 👇
+/// event!(
+///     target: "db::pool",
+///     Level::WARN,
+///     error.message = fields::error_message(&e),
+///     error.details = fields::error_details(&e),
+///     error.source_chain = fields::error_source_chain(&e),
+///     error.type = fields::error_type_name(&e),
+///     "The connection was dropped"
+/// );
+/// ```
+///
+/// # Explicit parent span
+///
+/// By default, the event is a child of the current span.
+/// You can pick a different parent with the `parent:` argument, just like with `tracing::event!`:
+///
+/// ```rust
+/// use tracing::{span, Level};
+/// use tracing_log_error::log_error;
+///
+/// let task_span = span!(Level::INFO, "task");
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, parent: &task_span, level: Level::WARN, "The task failed");
+/// ```
+///
+/// `parent:` only determines where the event sits in the span tree: the level of
+/// the event is still the one passed via `level:` (or `ERROR`, if omitted), no matter
+/// the level of the parent span.
+/// `parent:`, `target:` and `level:` can be specified in any order.
+///
+/// # Backtraces
+///
+/// Pass `backtrace: true` to also record a backtrace in the `error.backtrace` field:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, backtrace: true, "The connection was dropped");
+/// ```
+///
+/// The backtrace is only computed if the event is going to be recorded.
+/// Check out [`fields::error_backtrace`] for more details on how it is captured
+/// and when the field is omitted.
+///
+/// With the `anyhow` feature enabled, the backtrace of an `anyhow::Error` is the one
+/// `anyhow` captured when the error was created, rather than the one of the `log_error!`
+/// call site. The field is omitted if `anyhow` didn't capture a backtrace.
+///
+/// # Root cause
+///
+/// Pass `root_cause: true` to record the deepest source of the error in the
+/// `error.root_cause` field:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, root_cause: true, "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_root_cause`] for more details.
+///
+/// # Breadcrumbs
+///
+/// Pass `context: <breadcrumbs>` to record where the error happened in the
+/// `error.context` field, without mangling the message:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Records `error.context = "POST /users > tenant=acme"`
+/// log_error!(e, context: &["POST /users", "tenant=acme"], "The request failed");
+/// ```
+///
+/// The breadcrumbs can be any slice of strings (e.g. `&[&str]` or `&Vec<String>`).
+/// The field is omitted if the slice is empty.
+/// Check out [`fields::error_context`] for more details.
+///
+/// # Summary
+///
+/// Pass `summary: true` to record the first line of the error message in the
+/// `error.summary` field, a clean single-line title for errors with multi-line messages:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "Invalid config\n  at line 3");
+/// log_error!(e, summary: true, "The configuration couldn't be loaded");
+/// ```
+///
+/// The full message is still recorded in the `error.message` field.
+/// Check out [`fields::error_summary`] for more details.
+///
+/// # Enum variant
+///
+/// Pass `variant: true` to record the name of the enum variant the error was built from
+/// in the `error.variant` field, e.g. to filter on which variant of a `thiserror` enum fired:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, variant: true, "The connection was dropped");
+/// ```
+///
+/// It's a best-effort guess, based on the `Debug` representation of the error.
+/// Check out [`fields::error_variant`] for more details.
+///
+/// # Fingerprint
+///
+/// Pass `fingerprint: true` to record a stable hash of the error type and of its
+/// normalized root cause in the `error.fingerprint` field, to group similar errors together:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, fingerprint: true, "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_fingerprint`] for more details.
+///
+/// # Duration
+///
+/// Pass `took: <duration>` to record how long the operation ran before failing, as a
+/// whole number of milliseconds in the `error.duration_ms` field:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let start = std::time::Instant::now();
+/// let e = std::io::Error::new(std::io::ErrorKind::TimedOut, "My error");
+/// log_error!(e, took: start.elapsed(), "The connection timed out");
+/// ```
+///
+/// The duration must be a [`core::time::Duration`].
+/// Check out [`fields::error_duration_ms`] for more details.
+///
+/// # Category
+///
+/// Pass `category: <category>` to record the category of the error in the
+/// `error.category` field, using its `Display` representation:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// enum Category {
+///     Network,
+///     Validation,
+///     Internal,
+/// }
+///
+/// impl std::fmt::Display for Category {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str(match self {
+///             Category::Network => "network",
+///             Category::Validation => "validation",
+///             Category::Internal => "internal",
+///         })
+///     }
+/// }
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "My error");
+/// // Records `error.category = "network"`
+/// log_error!(e, category: Category::Network, "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_category`] for more details.
+///
+/// # Chain depth
+///
+/// Pass `chain_depth: true` to record the number of sources of the error in the
+/// `error.chain_depth` field, as a number:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, chain_depth: true, "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_chain_depth`] for more details.
+///
+/// # Indexed sources
+///
+/// Pass `indexed_sources: <n>` to record the first `n` sources of the error as
+/// distinct fields, `error.source_0` to `error.source_<n - 1>`, on top of the usual
+/// source chain:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, indexed_sources: 3, "The connection was dropped");
+/// ```
+///
+/// `n` must be an integer literal, up to [`fields::MAX_INDEXED_SOURCES`].
+/// Fields are only recorded for the sources that exist: nothing is recorded beyond
+/// the root cause.
+/// Each field is easy to query on its own, but every distinct field name has a cost
+/// in most backends—keep `n` small.
+/// Check out [`fields::error_sources_indexed`] for more details.
+///
+/// # Provided values
+///
+/// On nightly, errors can expose typed context via `Error::provide`.
+/// Pass `provided: <type> as <field name>` to record the value of that type provided by
+/// the error, or by one of its sources, using its `Debug` representation:
+///
+/// ```rust,ignore
+/// #![feature(error_generic_member_access)]
+/// use std::error::{Error, Request};
+/// use tracing_log_error::log_error;
+///
+/// #[derive(Debug)]
+/// struct RequestId(u64);
+///
+/// #[derive(Debug)]
+/// struct Timeout(RequestId);
+///
+/// impl std::fmt::Display for Timeout {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str("The request timed out")
+///     }
+/// }
+///
+/// impl Error for Timeout {
+///     fn provide<'a>(&'a self, request: &mut Request<'a>) {
+///         request.provide_ref::<RequestId>(&self.0);
+///     }
+/// }
+///
+/// let e = Timeout(RequestId(42));
+/// // Records `request_id = RequestId(42)`
+/// log_error!(e, provided: RequestId as request_id, "The request failed");
+/// ```
+///
+/// The field is omitted if no value of that type is provided.
+/// Requires the `nightly` feature, and a nightly compiler.
+/// Check out `fields::error_provided` for more details.
+///
+/// # Call site
+///
+/// Pass `caller: true` to record where `log_error!` was invoked, in the
+/// `code.filepath`, `code.lineno` and `code.column` fields:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, caller: true, "The connection was dropped");
+/// ```
+///
+/// Unlike a backtrace, the call site is known at compile-time: it's always
+/// available and cheap to record.
+///
+/// # Truncated message
+///
+/// Pass `max_message_len: <n>` to keep at most `n` characters of the `Display`
+/// representation of the error in the `error.message` field:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, max_message_len: 512, "The query failed");
+/// ```
+///
+/// `error.details` is unaffected, so the full representation is still recorded there.
+/// It can't be combined with `json: true`: the last one wins.
+/// Check out [`fields::error_message_truncated`] for more details.
+///
+/// # Pretty-printed details
+///
+/// Pass `pretty: true` to record the `Debug` representation of the error in the
+/// `error.details` field using the alternate format (`{:#?}`), spread over multiple lines.
+/// It's easier on the eyes when reading console logs, harder to parse for machines:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, pretty: true, "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_details_pretty`] for more details.
+///
+/// # Skipping redundant details
+///
+/// Pass `skip_redundant_details: true` to omit the `error.details` field when the `Debug`
+/// representation of the error is the same as its `Display` representation:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, skip_redundant_details: true, "The connection was dropped");
+/// ```
+///
+/// Both representations have to be computed to compare them, so the check only runs
+/// if the flag is set and the event is enabled.
+/// It can't be combined with `pretty: true`: the last one wins.
+/// Check out [`fields::error_details_unless_redundant`] for more details.
+///
+/// # Custom details
+///
+/// If the `Debug` representation of your error is expensive to compute or may leak
+/// sensitive data, pass `details: <expr>` to record something else in `error.details`:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, details: format!("kind={:?}", e.kind()), "The connection was dropped");
+/// ```
+///
+/// The expression must implement `Display`, and it's only evaluated if the event is enabled.
+/// The other fields (e.g. `error.message` or `error.source_chain`) are still computed
+/// from the error.
+/// It can't be combined with `pretty: true` or `skip_redundant_details: true`: the last one wins.
+///
+/// # Truncated details
+///
+/// Pass `max_details_len: <n>` to keep at most `n` characters of the `Debug`
+/// representation of the error in the `error.details` field:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, max_details_len: 2048, "The query failed");
+/// ```
+///
+/// If the details were cut short, `error.details_truncated = true` is recorded as well.
+/// The field is omitted otherwise.
+/// It can't be combined with `pretty: true`, `skip_redundant_details: true` or `details:`:
+/// the last one wins.
+/// Check out [`fields::error_details_truncated`] for more details.
+///
+/// # Verbose fields at the `ERROR` level only
+///
+/// Pass `verbose_at_error_only: true` to skip the `error.details` and `error.source_chain`
+/// fields unless the event is emitted at the `ERROR` level:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Only `error.message` and `error.type` 👇
+/// log_error!(e, level: Level::WARN, verbose_at_error_only: true, "The connection was dropped");
+/// ```
+///
+/// The skipped fields are not computed at all.
+/// The flag applies to the final level of the event, wherever it appears among the
+/// arguments: it works with runtime levels and with the default level too.
+///
+/// # A single JSON field
+///
+/// Pass `json: true` to record the message, the details and the source chain of the
+/// error as a single JSON object in the `error` field, rather than in three separate fields:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// # #[cfg(feature = "json")]
+/// log_error!(e, json: true, "The connection was dropped");
+/// ```
+///
+/// The other fields (e.g. `error.type`) are recorded as usual.
+/// Requires the `json` feature.
+/// Check out `fields::error_json` for more details on the representation.
+///
+/// # Structured source chain
+///
+/// By default, the chain of error sources is recorded as a single string.
+/// Pass `source_chain: structured` to record it as a list, with one entry per source:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, source_chain: structured, "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_source_chain_vec`] for more details.
+///
+/// # Pre-built source chain
+///
+/// If you've already collected the sources of the error (e.g. at an earlier boundary),
+/// pass them via `source_chain: <sources>` to skip walking the chain again:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let sources: Vec<String> = vec!["Connection reset".into()];
+/// log_error!(e, source_chain: &sources, "The connection was dropped");
+/// ```
+///
+/// `<sources>` can be anything that implements `IntoIterator`, as long as the items
+/// implement `Display`.
+/// Only the `error.source_chain` field is affected: the other fields are still computed
+/// from the error.
+/// Check out [`fields::error_source_chain_from`] for more details.
+///
+/// # Omitting an empty source chain
+///
+/// By default, `error.source_chain` is recorded even if the error has no sources—as an
+/// empty string.
+/// Pass `omit_empty_source_chain: true` to skip the field in that case:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // No `error.source_chain` field 👇
+/// log_error!(e, omit_empty_source_chain: true, "The connection was dropped");
+/// ```
+///
+/// It works with all the representations of the source chain (e.g. `source_chain: structured`),
+/// wherever it appears among the arguments.
+///
+/// # Skipping the source chain
+///
+/// Walking the sources of a deeply nested error, and formatting each of them, is the
+/// most expensive part of `log_error!`.
+/// On hot paths where the top-level message is enough, pass `skip_source_chain: true`:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // No `error.source_chain` field, and no call to `Error::source` 👇
+/// log_error!(e, skip_source_chain: true, "The connection was dropped");
+/// ```
+///
+/// The chain of sources isn't walked at all, rather than walked and discarded.
+/// The opt-in fields that need the sources (e.g. `root_cause: true` or `chain_depth: true`)
+/// still walk them, if you ask for them.
+///
+/// # Limiting the depth of the source chain
+///
+/// Pass `max_source_depth: <n>` to stop walking the chain of sources after `n` entries:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, max_source_depth: 5, "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_source_chain_capped`] for more details.
+///
+/// # Collapsing repeated sources
+///
+/// Pass `dedupe_sources: true` to collapse consecutive sources with the same
+/// message into a single entry in the source chain:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, dedupe_sources: true, "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_source_chain_deduped`] for more details.
+///
+/// # Custom separator for the source chain
+///
+/// Pass `source_sep: <separator>` to join the sources with a separator of your choice,
+/// instead of recording one `- <source>` line per source:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, source_sep: " -> ", "The connection was dropped");
+/// ```
+///
+/// Check out [`fields::error_source_chain_with_sep`] for more details.
+///
+/// # Type names in the source chain
+///
+/// Pass `with_types: true` to prefix each entry of the source chain with the name of
+/// its type. The chain then starts from the error itself:
+///
+/// ```rust
+/// use tracing_log_error::log_error;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Records `- [std::io::error::Error] My error\n`
+/// log_error!(e, with_types: true, "The connection was dropped");
+/// ```
+///
+/// Sources are trait objects: their concrete type is lost, and they are recorded as
+/// `[dyn core::error::Error]`.
+/// Check out [`fields::error_source_chain_with_types`] for more details.
+///
+/// `source_chain`, `skip_source_chain`, `max_source_depth`, `dedupe_sources`, `source_sep`
+/// and `with_types` can't be combined: the last one wins.
+/// They don't affect the JSON object recorded with `json: true`.
+#[macro_export]
+macro_rules! log_error {
+    ($msg:literal $(, $msg_arg:expr)* ; $err:expr $(, $($arg:tt)+)?) => (
+        $crate::log_error!($err, $($($arg)+,)? $msg $(, $msg_arg)*)
+    );
+    ($err:expr $(, $($arg:tt)*)?) => (
+        $crate::__log_error_default_level!([@start [$err] []] $($($arg)*)?)
+    );
+}
+
+/// Log an error via [`log_error!`], then evaluate to the error itself.
+///
+/// It comes in handy when you want to log an error and return it right away:
+///
+/// ```rust
+/// use tracing_log_error::log_error_return;
+///
+/// fn connect() -> Result<(), std::io::Error> {
+///     let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+///     Err(log_error_return!(e, "The connection was dropped"))
+/// }
+/// ```
+///
+/// The error expression is evaluated exactly once.
+/// `log_error_return!` accepts the same arguments as [`log_error!`].
+#[macro_export]
+macro_rules! log_error_return {
+    ($err:expr $(, $($arg:tt)*)?) => (
+        match $err {
+            err => {
+                $crate::log_error!(err $(, $($arg)*)?);
+                err
+            }
+        }
+    );
+}
+
+/// Log an error via [`log_error!`], then panic.
+///
+/// It's meant for truly unrecoverable conditions: the structured event is emitted
+/// before the panic unwinds (or aborts) the process, with all the error fields that
+/// `expect` would lose.
+///
+/// ```rust,should_panic
+/// use tracing_log_error::log_error_panic;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Panics with `The configuration is unreadable: My error`
+/// log_error_panic!(e, "The configuration is {}", "unreadable");
+/// ```
+///
+/// The panic message is the event message followed by the `Display` representation of
+/// the error, as for [`Result::expect`].
+/// Without a message, it panics with the `Display` representation of the error alone.
+/// The panic is raised from your code, so it points at the `log_error_panic!` invocation.
+///
+/// The error expression and the format arguments are evaluated exactly once.
+/// Unlike `log_error!`, `log_error_panic!` doesn't accept named arguments or custom fields:
+/// use `log_error!` followed by `panic!` if you need them.
+#[macro_export]
+macro_rules! log_error_panic {
+    ($err:expr $(,)?) => (
+        match $err {
+            err => {
+                $crate::log_error!(err);
+                ::core::panic!("{}", err)
+            }
+        }
+    );
+    ($err:expr, $fmt:literal $(, $fmt_arg:expr)* $(,)?) => (
+        match ($err, ::core::format_args!($fmt $(, $fmt_arg)*)) {
+            (err, message) => {
+                $crate::log_error!(err, "{}", message);
+                ::core::panic!("{}: {}", message, err)
+            }
+        }
+    );
+}
+
+/// Build a closure that logs an error via [`log_error!`], then returns it.
+///
+/// It's designed for `map_err`, to spare you a closure at every fallible call site:
+///
+/// ```rust
+/// use tracing_log_error::log_map_err;
+///
+/// fn read_config() -> Result<String, std::io::Error> {
+///     std::fs::read_to_string("config.toml").map_err(log_map_err!("Failed to read the config"))
+/// }
+/// ```
+///
+/// Use `into: <type>` as the first argument to convert the error via `Into` after
+/// logging it:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::log_map_err;
+///
+/// struct ConfigError(std::io::Error);
+///
+/// impl From<std::io::Error> for ConfigError {
+///     fn from(e: std::io::Error) -> Self {
+///         Self(e)
+///     }
+/// }
+///
+/// fn read_config() -> Result<String, ConfigError> {
+///     let config = std::fs::read_to_string("config.toml")
+///         .map_err(log_map_err!(into: ConfigError, level: Level::WARN, path = "config.toml"))?;
+///     Ok(config)
+/// }
+/// ```
+///
+/// Other than that, `log_map_err!` accepts the same arguments as [`log_error!`],
+/// minus the error itself.
+#[macro_export]
+macro_rules! log_map_err {
+    (into: $ty:ty $(, $($arg:tt)*)?) => (
+        |err| -> $ty {
+            $crate::log_error!(err $(, $($arg)*)?);
+            ::core::convert::Into::into(err)
+        }
+    );
+    ($($arg:tt)*) => (
+        |err| $crate::log_error_return!(err, $($arg)*)
+    );
+}
+
+/// Log the outcome of a fallible operation, then evaluate to the `Result` itself.
+///
+/// An `INFO` event is emitted on `Ok`, while an `ERROR` event is emitted via
+/// [`log_error!`] on `Err`, with all the usual error fields:
+///
+/// ```rust
+/// use tracing_log_error::log_result;
+///
+/// fn save() -> Result<(), std::io::Error> {
+///     // [...]
+///     # Ok(())
+/// }
+///
+/// let outcome = log_result!(save(), ok: "Saved", err: "Failed to save");
+/// ```
+///
+/// The `ok:` message is optional: without it, the `INFO` event only carries your custom
+/// fields.
+/// Custom fields go last, and they're recorded on both events:
+///
+/// ```rust
+/// use tracing_log_error::log_result;
+///
+/// # fn save() -> Result<(), std::io::Error> { Ok(()) }
+/// let user_id = 42;
+/// let outcome = log_result!(save(), err: "Failed to save", user_id, tenant = "acme");
+/// ```
+#[macro_export]
+macro_rules! log_result {
+    ($result:expr, ok: $ok:expr, err: $err:expr $(, $($field:tt)*)?) => (
+        match $result {
+            ::core::result::Result::Ok(value) => {
+                ::tracing::event!(::tracing::Level::INFO, { $($($field)*)? }, "{}", $ok);
+                ::core::result::Result::Ok(value)
+            }
+            ::core::result::Result::Err(e) => {
+                $crate::log_error!(e, { $($($field)*)? }, "{}", $err);
+                ::core::result::Result::Err(e)
+            }
+        }
+    );
+    ($result:expr, err: $err:expr $(, $($field:tt)*)?) => (
+        match $result {
+            ::core::result::Result::Ok(value) => {
+                // `tracing` only accepts a braced set of fields without a message
+                // if the target is spelled out.
+                ::tracing::event!(
+                    target: ::core::module_path!(),
+                    ::tracing::Level::INFO,
+                    { $($($field)*)? }
+                );
+                ::core::result::Result::Ok(value)
+            }
+            ::core::result::Result::Err(e) => {
+                $crate::log_error!(e, { $($($field)*)? }, "{}", $err);
+                ::core::result::Result::Err(e)
+            }
+        }
+    );
+}
+
+/// A variant of [`log_error!`] that uses a custom prefix for the error fields,
+/// instead of `error`.
+///
+/// ```rust
+/// use tracing_log_error::log_error_with_prefix;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// // Records `err.message`, `err.details`, `err.source_chain` and `err.type`
+/// log_error_with_prefix!("err", e, "The connection was dropped");
+/// ```
+///
+/// The prefix must be a string literal. Other than that, `log_error_with_prefix!`
+/// accepts the same arguments as [`log_error!`].
+///
+/// # Using the same prefix everywhere
+///
+/// Define your own macro to avoid repeating the prefix at every call site:
+///
+/// ```rust
+/// macro_rules! log_error {
+///     ($($arg:tt)*) => {
+///         tracing_log_error::log_error_with_prefix!("err", $($arg)*)
+///     };
+/// }
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, "The connection was dropped");
+/// ```
+#[macro_export]
+macro_rules! log_error_with_prefix {
+    ($prefix:literal, $err:expr $(, $($arg:tt)*)?) => (
+        $crate::__log_error_default_level!([@start [$err] [$prefix]] $($($arg)*)?)
+    );
+}
+
+/// A variant of [`log_error!`] that emits at most one event per `interval` for
+/// each call site.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use tracing_log_error::log_error_throttled;
+///
+/// for _ in 0..1000 {
+///     let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+///     // Only the first iteration emits an event
+///     log_error_throttled!(Duration::from_secs(1), e, "The connection was dropped");
+/// }
+/// ```
+///
+/// The first event emitted after some were suppressed records how many of them
+/// were held back in the `suppressed_count` field.
+/// The field is omitted if nothing was suppressed.
+///
+/// Suppressed invocations don't evaluate the error expression, nor any of the
+/// other arguments except `interval`.
+/// Other than that, `log_error_throttled!` accepts the same arguments as [`log_error!`].
+///
+/// Check out [`Throttle`] if you need to share the same limit across multiple call sites.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! log_error_throttled {
+    ($interval:expr, $err:expr $(, $($arg:tt)*)?) => ($crate::__log_error_gate!({
+        static THROTTLE: $crate::Throttle = $crate::Throttle::new();
+        if let ::core::option::Option::Some(suppressed) = THROTTLE.check($interval) {
+            $crate::__log_error_default_level!(
+                [@start [$err] []]
+                @extra [suppressed_count = (suppressed > 0).then_some(suppressed),]
+                $($($arg)*)?
+            )
+        }
+    }));
+}
+
+/// A variant of [`log_error!`] for errors wrapped in a [`LoggedOnce`].
+///
+/// ```rust
+/// use tracing_log_error::{log_error_once, LoggedOnce};
+///
+/// let e = LoggedOnce::new(std::io::Error::new(std::io::ErrorKind::Other, "My error"));
+/// // Emitted at the `ERROR` level
+/// log_error_once!(e, "The connection was dropped");
+/// // Emitted at the `DEBUG` level, with `already_logged = true`
+/// log_error_once!(e, "The request failed");
+/// ```
+///
+/// The first invocation for a given error marks it as logged and emits an `ERROR` event.
+/// The following ones emit a `DEBUG` event, with an `already_logged` field set to `true`.
+/// The error fields are the ones of the wrapped error: `error.type` is the type of the
+/// wrapped error, not `LoggedOnce<...>`.
+///
+/// `log_error_once!` accepts the same arguments as [`log_error!`].
+/// If you specify a level, it applies to all invocations, first one included.
+#[macro_export]
+macro_rules! log_error_once {
+    ($err:expr $(, $($arg:tt)*)?) => ($crate::__log_error_gate!({
+        let once = &$err;
+        if $crate::LoggedOnce::mark_logged(once) {
+            $crate::__log_error!(
+                @start [*$crate::LoggedOnce::get_ref(once)] [] [::tracing::Level::DEBUG]
+                @extra [already_logged = true,]
+                $($($arg)*)?
+            )
+        } else {
+            $crate::__log_error_default_level!(
+                [@start [*$crate::LoggedOnce::get_ref(once)] []]
+                $($($arg)*)?
+            )
+        }
+    }));
+}
+
+/// A variant of [`log_error!`] that only logs the error if a predicate over it holds,
+/// e.g. to keep expected errors out of your logs:
+///
+/// ```rust
+/// use std::io::ErrorKind;
+/// use tracing_log_error::log_error_if;
+///
+/// let e = std::io::Error::new(ErrorKind::NotFound, "My error");
+/// // Nothing is logged 👇
+/// log_error_if!(e, when: |e| e.kind() != ErrorKind::NotFound, "Failed to open the file");
+/// ```
+///
+/// The predicate receives a reference to the error and it's evaluated before anything else.
+/// If it returns `false`, none of the other arguments are evaluated.
+/// Other than `when:`, which must come first, `log_error_if!` accepts the same arguments as
+/// [`log_error!`].
+#[macro_export]
+macro_rules! log_error_if {
+    ($err:expr, when: $predicate:expr $(, $($arg:tt)*)?) => (
+        $crate::__log_error_gate!(match &$err {
+            err => {
+                if $crate::as_error::check_predicate(err, $predicate) {
+                    $crate::log_error!(*err $(, $($arg)*)?);
+                }
+            }
+        })
+    );
+}
+
+/// Log an error twice: a terse `ERROR` event and a verbose `TRACE` event, so that
+/// the expensive diagnostics can be turned on selectively via filters.
+///
+/// ```rust
+/// use tracing_log_error::log_error_tiered;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error_tiered!(e, "The connection was dropped");
+/// ```
+///
+/// - The `ERROR` event records `error.message`, `error.type` and `error.root_cause`.
+/// - The `TRACE` event records the usual fields of [`log_error!`], as well as `error.backtrace`.
+///
+/// The `TRACE` event is skipped entirely if nobody is interested in it: the details and
+/// the source chain aren't formatted, and the backtrace isn't captured.
+///
+/// The error expression is evaluated exactly once.
+/// Other than `level:`, `log_error_tiered!` accepts the same arguments as [`log_error!`],
+/// and applies them to both events.
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! log_error_tiered {
+    ($err:expr $(, $($arg:tt)*)?) => (
+        $crate::__log_error_gate!(match &$err {
+            err => {
+                $crate::__log_error!(
+                    @start [*err] [] [::tracing::Level::ERROR]
+                    @skip_details skip_source_chain: true, root_cause: true $(, $($arg)*)?
+                );
+                $crate::__log_error!(
+                    @start [*err] [] [::tracing::Level::TRACE]
+                    @skip_on_error backtrace: true $(, $($arg)*)?
+                );
+            }
+        })
+    );
+}
+
+/// Log a batch of errors as a single event.
+///
+/// ```rust
+/// use tracing_log_error::log_errors;
+///
+/// let errors = vec![
+///     std::io::Error::new(std::io::ErrorKind::InvalidInput, "Missing name"),
+///     std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid email"),
+/// ];
+/// log_errors!(&errors, "The sign-up form is invalid");
+/// ```
+///
+/// The first argument can be anything that implements `IntoIterator`, as long as
+/// the items implement [`std::error::Error`].
+/// The event records the message and the details of each error in the `errors` field,
+/// and the number of errors in the `error_count` field.
+/// Check out [`fields::error_batch`] for more details on the representation.
+///
+/// No event is emitted if there are no errors.
+/// The errors are only formatted if the level is enabled: the first argument isn't
+/// evaluated otherwise.
+/// `log_errors!` supports the same syntax as [`log_error!`] for custom fields and custom
+/// levels:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::log_errors;
+///
+/// let errors: Vec<std::io::Error> = Vec::new();
+/// log_errors!(errors, level: Level::WARN, tenant = "acme", "The sign-up form is invalid");
+/// ```
+#[macro_export]
+macro_rules! log_errors {
+    ($errors:expr, level: $lvl:expr $(, $($arg:tt)*)?) => (
+        // The batch is built ahead of the event, to know if it's empty: it's only
+        // worth it if someone is listening.
+        if ::tracing::enabled!($lvl) {
+            match $crate::fields::error_batch($errors) {
+                batch => {
+                    if !batch.is_empty() {
+                        ::tracing::event!(
+                            $lvl,
+                            { $crate::fields::ERRORS } = ::tracing::field::debug(&batch),
+                            { $crate::fields::ERROR_COUNT } = batch.len(),
+                            $($($arg)*)?
+                        );
+                    }
+                }
+            }
+        }
+    );
+    ($errors:expr $(, $($arg:tt)*)?) => (
+        $crate::log_errors!($errors, level: ::tracing::Level::ERROR $(, $($arg)*)?)
+    );
+}
+
+/// A variant of [`log_error!`] for failures that aren't modeled as an error type,
+/// e.g. a `String` or a status enum.
+///
+/// ```rust
+/// use tracing_log_error::log_failure;
+///
+/// let status = format!("Quota exceeded for tenant {}", "acme");
+/// log_failure!(status, "The upload was rejected");
+/// ```
+///
+/// The failure can be anything that implements `Display`:
+///
+/// - Its `Display` representation is recorded in the `error.message` field.
+/// - Its `Debug` representation, if it has one, is recorded in the `error.details` field.
+///
+/// There are no sources to walk, so the `error.source_chain` field is omitted, as well
+/// as `error.type`.
+/// Other than that, `log_failure!` supports the same syntax as [`log_error!`],
+/// minus the options that require an error (e.g. `backtrace: true`).
+#[macro_export]
+macro_rules! log_failure {
+    ($failure:expr $(, $($arg:tt)*)?) => (
+        $crate::__log_error!(@start_failure [$failure] [] [::tracing::Level::ERROR] $($($arg)*)?)
+    );
+}
+
+/// A companion to [`log_error!`] for `Option`s, where `None` signals a failure.
+///
+/// If the value is `None`, an event is emitted via `tracing::event!`.
+/// The `Option` is then returned unchanged, so you can use `log_none!` inline:
+///
+/// ```rust
+/// use tracing_log_error::log_none;
+///
+/// fn config_path() -> Option<String> {
+///     let path = log_none!(std::env::var("CONFIG_PATH").ok(), "Missing config path")?;
+///     Some(path)
+/// }
+/// ```
+///
+/// There is no error to capture, so the event doesn't have any of the fields in
+/// [the `fields` module](crate::fields).
+/// Other than that, `log_none!` supports the same syntax as [`log_error!`] for custom
+/// fields and custom levels:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::log_none;
+///
+/// let user_id: Option<u64> = None;
+/// log_none!(user_id, level: Level::WARN, tenant = "acme", "Unknown user");
+/// ```
+#[macro_export]
+macro_rules! log_none {
+    ($opt:expr, level: $lvl:expr, $($arg:tt)+) => (
+        match $opt {
+            ::core::option::Option::Some(value) => ::core::option::Option::Some(value),
+            ::core::option::Option::None => {
+                ::tracing::event!($lvl, $($arg)+);
+                ::core::option::Option::None
+            }
+        }
+    );
+    ($opt:expr, $($arg:tt)+) => (
+        $crate::log_none!($opt, level: ::tracing::Level::ERROR, $($arg)+)
+    );
+}
+
+/// A companion to [`log_error!`] for the result of acquiring a lock, e.g. via
+/// [`Mutex::lock`](std::sync::Mutex::lock).
+///
+/// If the lock is poisoned, the error is logged as a [`PoisonedLock`]: the event
+/// records `error.message = "mutex poisoned"` (or `"rwlock poisoned"`), with the type
+/// of the guarded data in `error.details`.
+/// The result is then returned unchanged, so you can decide how to recover:
+///
+/// ```rust
+/// use std::sync::{Mutex, PoisonError};
+/// use tracing_log_error::log_poison;
+///
+/// let cache: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+/// let guard = log_poison!(cache.lock(), "The cache lock is poisoned")
+///     .unwrap_or_else(PoisonError::into_inner);
+/// ```
+///
+/// `log_poison!` accepts the same arguments as [`log_error!`].
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! log_poison {
+    ($result:expr $(, $($arg:tt)*)?) => (
+        match $result {
+            ::core::result::Result::Ok(guard) => ::core::result::Result::Ok(guard),
+            ::core::result::Result::Err(e) => {
+                $crate::log_error!($crate::PoisonedLock::from(&e) $(, $($arg)*)?);
+                ::core::result::Result::Err(e)
+            }
+        }
+    );
+}
+
+/// A variant of [`log_error!`] that emits the event to the given [`Dispatch`](tracing::Dispatch),
+/// rather than to the default subscriber:
+///
+/// ```rust
+/// use tracing::Dispatch;
+/// use tracing_log_error::log_error_to;
+///
+/// # let subscriber = tracing::subscriber::NoSubscriber::default();
+/// // E.g. the dispatch of a component with its own, isolated, logging
+/// let dispatch = Dispatch::new(subscriber);
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error_to!(&dispatch, e, "The connection was dropped");
+/// ```
+///
+/// The dispatch is set as the default for the duration of the call, via
+/// [`tracing::dispatcher::with_default`]: if the event has no explicit `parent:`,
+/// its parent is the current span of that dispatch.
+///
+/// Other than the dispatch, `log_error_to!` accepts the same arguments as [`log_error!`].
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! log_error_to {
+    ($dispatch:expr, $($arg:tt)+) => (
+        ::tracing::dispatcher::with_default($dispatch, || $crate::log_error!($($arg)+))
+    );
+}
+
+/// A variant of `tracing::span!` that declares the error fields recorded by
+/// [`fields::record_on_current_span`] and [`fields::mark_span_errored`], with an
+/// [`Empty`](tracing::field::Empty) value.
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::{error_span, fields};
+///
+/// let span = error_span!(Level::INFO, "request", tenant = "acme");
+/// let _guard = span.enter();
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// fields::record_on_current_span(&e);
+/// ```
+///
+/// `tracing` has to know all the fields of a span upfront, as tokens: a macro can't
+/// expand to a list of fields in the middle of a `span!` or `#[instrument]` invocation.
+/// `error_span!` takes care of the whole span instead, so that the error field names
+/// are defined in one place.
+/// The level and the name are followed by your own fields, using the same syntax as
+/// `tracing::span!`.
+#[macro_export]
+macro_rules! error_span {
+    ($lvl:expr, $name:expr $(, $($fields:tt)*)?) => (
+        ::tracing::span!(
+            $lvl,
+            $name,
+            { $crate::fields::ERROR_MESSAGE } = ::tracing::field::Empty,
+            { $crate::fields::ERROR_DETAILS } = ::tracing::field::Empty,
+            { $crate::fields::ERROR_SOURCE_CHAIN } = ::tracing::field::Empty,
+            { $crate::fields::ERROR_TYPE } = ::tracing::field::Empty,
+            { $crate::fields::ERROR_OCCURRED } = ::tracing::field::Empty,
+            $($($fields)*)?
+        )
+    );
+}
+
+/// The value for `json: true`.
+///
+/// Not part of the public API.
+#[cfg(feature = "json")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_json {
+    ($err:expr) => {
+        $crate::fields::error_json($err)
+    };
+}
+
+/// `json: true` is rejected with a clear error, rather than a missing function.
+///
+/// Not part of the public API.
+#[cfg(not(feature = "json"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_json {
+    ($err:expr) => {
+        ::core::compile_error!("`json: true` requires the `json` feature of `tracing_log_error`")
+    };
+}
+
+/// Picks the level for invocations without `level:`: a single `ERROR` event, unless
+/// the `runtime-default-level` feature is enabled.
+///
+/// Not part of the public API.
+#[cfg(not(feature = "runtime-default-level"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_default_level {
+    ([$($start:tt)*] $($rest:tt)*) => (
+        $crate::__log_error!($($start)* [::tracing::Level::ERROR] $($rest)*)
+    );
+}
+
+/// The level is read at runtime, see [`set_default_level`].
+///
+/// Not part of the public API.
+#[cfg(feature = "runtime-default-level")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_default_level {
+    ([$($start:tt)*] $($rest:tt)*) => (
+        $crate::__log_error!($($start)* [@dyn $crate::default_level()] $($rest)*)
+    );
+}
+
+/// Expands to the event, unless error logging is stripped from this build—see the
+/// `disable-in-release` feature.
+///
+/// Not part of the public API.
+#[cfg(not(all(feature = "disable-in-release", not(debug_assertions))))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_gate {
+    ($($event:tt)*) => (
+        $($event)*
+    );
+}
+
+/// The event is kept in a branch that's never taken: the arguments are still
+/// type-checked (and count as used), but the optimizer removes the whole thing.
+///
+/// A `let _ = (&$err, ..);` no-op would evaluate the error expression, and it can't
+/// cover the custom fields or the message arguments, which aren't expressions on
+/// their own (e.g. `field = 42` or `"{}", x`). Within `if false { .. }`, nothing
+/// is evaluated.
+///
+/// Not part of the public API.
+#[cfg(all(feature = "disable-in-release", not(debug_assertions)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_gate {
+    ($($event:tt)*) => (
+        if false {
+            $($event)*
+        }
+    );
+}
+
+/// Parses the named arguments accepted by [`log_error!`] (e.g. `backtrace: true`)
+/// ahead of the custom fields and the message, then emits the event.
+///
+/// While parsing, the state is carried around as a sequence of bracketed groups:
+/// the error, the field name prefix (if one was specified), the level, the target and the parent span (if they were specified), the tokens for the `error.message`, `error.details` and
+/// `error.source_chain` fields, and the tokens for any additional error field.
+///
+/// Not part of the public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error {
+    (@start [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] $($rest:tt)*) => (
+        $crate::__log_error_gate!($crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [] []
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_MESSAGE) } = $crate::fields::error_message(&$err),]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_DETAILS) } = $crate::fields::error_details(&$err),]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_SOURCE_CHAIN) } = $crate::fields::error_source_chain($crate::__log_error_as_error!($err)),]
+            [
+                @on_error
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_TYPE) } = $crate::fields::error_type_name(&$err),
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_RETRYABLE) } = $crate::__log_error_as_error!(@retryable $err),
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_OS_CODE) } = $crate::__log_error_as_error!(@os_code $err),
+            ]
+            $($rest)*
+        ))
+    );
+    // Used by `log_failure!`: there is no source chain, nor a meaningful type name.
+    (@start_failure [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] $($rest:tt)*) => (
+        $crate::__log_error_gate!($crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [] []
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_MESSAGE) } = $crate::fields::error_message(&$err),]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_DETAILS) } = $crate::__log_error_as_error!(@failure_details $err),]
+            []
+            []
+            $($rest)*
+        ))
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        level: :: $first:ident $(:: $segment:ident)+ $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [:: $first $(:: $segment)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        level: $first:ident $(:: $segment:ident)+ $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$first $(:: $segment)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    // Any other expression might only be known at runtime, see the `@dyn` arm below.
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        level: $new_lvl:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [@dyn $new_lvl] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        target: $new_target:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [target: $new_target,] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        parent: $new_parent:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [parent: $new_parent,] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        backtrace: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error_backtrace!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        backtrace: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        root_cause: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_ROOT_CAUSE) } = $crate::fields::error_root_cause($crate::__log_error_as_error!($err)),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        root_cause: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        context: $breadcrumbs:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_CONTEXT) } = $crate::fields::error_context($breadcrumbs),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        summary: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_SUMMARY) } = $crate::fields::error_summary($crate::__log_error_as_error!($err)),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        summary: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        variant: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_VARIANT) } = $crate::fields::error_variant(&$err),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        variant: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        fingerprint: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_FINGERPRINT) } = $crate::fields::__error_fingerprint(
+                    $crate::fields::error_type_name(&$err),
+                    $crate::__log_error_as_error!($err),
+                ),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        fingerprint: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        took: $took:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_DURATION_MS) } = $crate::fields::error_duration_ms($took),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        category: $category:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_CATEGORY) } = $crate::fields::error_category($category),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        indexed_sources: $n:tt $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @indexed_sources [$n]
+            [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    // `macro_rules!` can't count, so we map each supported number of sources to
+    // the list of indexes to record.
+    (@indexed_sources [0] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [] $($rest)*));
+    (@indexed_sources [1] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [0] $($rest)*));
+    (@indexed_sources [2] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [0 1] $($rest)*));
+    (@indexed_sources [3] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [0 1 2] $($rest)*));
+    (@indexed_sources [4] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [0 1 2 3] $($rest)*));
+    (@indexed_sources [5] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [0 1 2 3 4] $($rest)*));
+    (@indexed_sources [6] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [0 1 2 3 4 5] $($rest)*));
+    (@indexed_sources [7] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [0 1 2 3 4 5 6] $($rest)*));
+    (@indexed_sources [8] $($rest:tt)*) => ($crate::__log_error!(@indexed_sources_for [0 1 2 3 4 5 6 7] $($rest)*));
+    (
+        @indexed_sources_for [$($i:tt)*]
+        [$err:expr] $prefix:tt [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        $($rest:tt)*
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] $prefix [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                $(
+                    { $crate::__log_error_field_name!($prefix ERROR_SOURCES_INDEXED $i) } = $crate::fields::error_sources_indexed(
+                        $crate::__log_error_as_error!($err),
+                        $crate::fields::MAX_INDEXED_SOURCES
+                    ).nth($i),
+                )*
+            ]
+            $($rest)*
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        chain_depth: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_CHAIN_DEPTH) } = $crate::fields::error_chain_depth($crate::__log_error_as_error!($err)),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        chain_depth: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        caller: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] CODE_FILEPATH) } = $crate::fields::caller_location().file(),
+                { $crate::__log_error_field_name!([$($prefix)*] CODE_LINENO) } = $crate::fields::caller_location().line(),
+                { $crate::__log_error_field_name!([$($prefix)*] CODE_COLUMN) } = $crate::fields::caller_location().column(),
+            ]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        caller: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        max_message_len: $max_len:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_MESSAGE) } = $crate::fields::error_message_truncated(&$err, $max_len),]
+            [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        max_details_len: $max_len:expr $(, $($rest:tt)*)?
+    ) => (
+        // Both fields come from the same `error_details_truncated` call, made by
+        // whichever of the two is recorded first.
+        match $crate::fields::__LazyTruncatedDetails::new() {
+            details => {
+                // Unused if the details are overridden afterwards, e.g. via `details:`
+                let _ = &details;
+                $crate::__log_error!(
+                    @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*]
+                    [
+                        { $crate::__log_error_field_name!([$($prefix)*] ERROR_DETAILS) } = details
+                            .get(|| $crate::fields::error_details_truncated(&$err, $max_len))
+                            .details(),
+                        { $crate::__log_error_field_name!([$($prefix)*] ERROR_DETAILS_TRUNCATED) } = details
+                            .get(|| $crate::fields::error_details_truncated(&$err, $max_len))
+                            .truncated(),
+                    ]
+                    [$($chain)*] [$($extra)*]
+                    $($($rest)*)?
+                )
+            }
+        }
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        verbose_at_error_only: true $(, $($rest:tt)*)?
+    ) => (
+        // The marker is resolved once the level is known, see the arms right after `@dyn`.
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [@verbose_at_error_only $($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        verbose_at_error_only: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        omit_empty_source_chain: true $(, $($rest:tt)*)?
+    ) => (
+        // The marker is resolved once all arguments have been parsed, see the arms right after `@dyn`.
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [@omit_empty_source_chain $($extra)*]
+            $($($rest)*)?
         )
     );
-    ($err:expr, level: $lvl:expr, { $($fields:tt)* }, $($arg:tt)+) => (
-        ::tracing::event!(
-            $lvl,
-            {{ $crate::fields::ERROR_MESSAGE }} = $crate::fields::error_message(&$err),
-            {{ $crate::fields::ERROR_DETAILS }} = $crate::fields::error_details(&$err),
-            {{ $crate::fields::ERROR_SOURCE_CHAIN }} = $crate::fields::error_source_chain(&$err),
-            { $($fields)* },
-            $($arg)+
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        omit_empty_source_chain: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        details: $new_details:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_DETAILS) } = ::tracing::field::display($new_details),]
+            [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        skip_redundant_details: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_DETAILS) } = $crate::fields::error_details_unless_redundant(&$err),]
+            [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        pretty: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_DETAILS) } = $crate::fields::error_details_pretty(&$err),]
+            [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        pretty: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_DETAILS) } = $crate::fields::error_details(&$err),]
+            [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        json: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*]
+            [{ $crate::__log_error_field_name!([$($prefix)*] ERROR_JSON) } = $crate::__log_error_json!($crate::__log_error_as_error!($err)),]
+            [] [] [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        source_chain: structured $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*]
+            [
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_SOURCE_CHAIN) } = ::tracing::field::debug(
+                    $crate::fields::error_source_chain_vec($crate::__log_error_as_error!($err))
+                ),
+            ]
+            [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        source_chain: $sources:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*]
+            [
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_SOURCE_CHAIN) } = $crate::fields::error_source_chain_from($sources),
+            ]
+            [$($extra)*]
+            $($($rest)*)?
+        )
+    );
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        skip_source_chain: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [] [$($extra)*]
+            $($($rest)*)?
         )
     );
-    ($err:expr, level: $lvl:expr, $($k:ident).+ = $($field:tt)*) => (
-        $crate::log_error!(
-            $err,
-            level: $lvl,
-            { $($k).+ = $($field)*}
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        skip_source_chain: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
         )
     );
-    ($err:expr, level: $lvl:expr, ?$($k:ident).+, $($field:tt)*) => (
-        $crate::log_error!(
-            $err,
-            level: $lvl,
-            { ?$($k).+, $($field)*}
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        max_source_depth: $max_depth:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*]
+            [
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_SOURCE_CHAIN) } = $crate::fields::error_source_chain_capped(
+                    $crate::__log_error_as_error!($err),
+                    $max_depth
+                ),
+            ]
+            [$($extra)*]
+            $($($rest)*)?
         )
     );
-    ($err:expr, level: $lvl:expr, %$($k:ident).+, $($field:tt)*) => (
-        $crate::log_error!(
-            $err,
-            level: $lvl,
-            { %$($k).+, $($field)*}
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        dedupe_sources: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*]
+            [
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_SOURCE_CHAIN) } = $crate::fields::error_source_chain_deduped($crate::__log_error_as_error!($err)),
+            ] [$($extra)*]
+            $($($rest)*)?
         )
     );
-    ($err:expr, level: $lvl:expr, ?$($k:ident).+) => (
-        $crate::log_error!($err, level: $lvl, ?$($k).+,)
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        with_types: true $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*]
+            [
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_SOURCE_CHAIN) } = $crate::fields::__error_source_chain_with_types(
+                    $crate::fields::error_type_name(&$err),
+                    $crate::__log_error_as_error!($err),
+                ),
+            ] [$($extra)*]
+            $($($rest)*)?
+        )
     );
-    ($err:expr, level: $lvl:expr, %$($k:ident).+) => (
-        $crate::log_error!($err, level: $lvl, %$($k).+,)
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        with_types: false $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($($rest)*)?
+        )
     );
-    ($err:expr, level: $lvl:expr, $($k:ident).+) => (
-        $crate::log_error!($err, level: $lvl, $($k).+,)
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        source_sep: $sep:expr $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*]
+            [
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_SOURCE_CHAIN) } = $crate::fields::error_source_chain_with_sep(
+                    $crate::__log_error_as_error!($err),
+                    $sep
+                ),
+            ]
+            [$($extra)*]
+            $($($rest)*)?
+        )
     );
-    ($err:expr, level: $lvl:expr, $($arg:tt)*) => (
-        $crate::log_error!($err, level: $lvl, { $($arg)* })
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        provided: $ty:ty as $($name:ident).+ $(, $($rest:tt)*)?
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                $($name).+ = $crate::__log_error_provided!($ty, $crate::__log_error_as_error!($err)),
+            ]
+            $($($rest)*)?
+        )
     );
-    ($err:expr, level: $lvl:expr) => (
-        $crate::log_error!($err, level: $lvl, { })
+    // Used by other macros of this crate to leave `error.details` out.
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        @skip_details $($rest:tt)*
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [] [$($chain)*] [$($extra)*]
+            $($rest)*
+        )
     );
-    ($err:expr, { $($fields:tt)* }, $($arg:tt)+) => (
-        $crate::log_error!($err, level: ::tracing::Level::ERROR, { $($fields)* }, $($arg)+)
+    // Used by other macros of this crate to skip the hook registered via `set_on_error`.
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [@on_error $($extra:tt)*]
+        @skip_on_error $($rest:tt)*
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($rest)*
+        )
     );
-    ($err:expr, $($k:ident).+ = $($field:tt)*) => (
-        $crate::log_error!(
-            $err,
-            level: ::tracing::Level::ERROR,
-            $($k).+ = $($field)*
+    // Additional fields injected by other macros of this crate, ahead of the user's arguments.
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        @extra [$($new_extra:tt)*] $($rest:tt)*
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [$($extra)* $($new_extra)*]
+            $($rest)*
         )
     );
-    ($err:expr, ?$($k:ident).+, $($field:tt)*) => (
-        $crate::log_error!(
-            $err,
-            level: ::tracing::Level::ERROR,
-            ?$($k).+,
-            $($field)*
+    // `tracing` requires the level of an event to be known at compile-time. A level
+    // that might only be known at runtime is matched against each of the possible
+    // levels, with one event per level.
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [@dyn $lvl:expr] $($rest:tt)*
+    ) => (
+        match $lvl {
+            ::tracing::Level::ERROR => $crate::__log_error!(@parse [$err] [$($prefix)*] [::tracing::Level::ERROR] $($rest)*),
+            ::tracing::Level::WARN => $crate::__log_error!(@parse [$err] [$($prefix)*] [::tracing::Level::WARN] $($rest)*),
+            ::tracing::Level::INFO => $crate::__log_error!(@parse [$err] [$($prefix)*] [::tracing::Level::INFO] $($rest)*),
+            ::tracing::Level::DEBUG => $crate::__log_error!(@parse [$err] [$($prefix)*] [::tracing::Level::DEBUG] $($rest)*),
+            _ => $crate::__log_error!(@parse [$err] [$($prefix)*] [::tracing::Level::TRACE] $($rest)*),
+        }
+    );
+    // `omit_empty_source_chain: true`: the source chain is only computed, and recorded,
+    // if the error has a source.
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*] [$($message:tt)*]
+        [$($details:tt)*]
+        [$({ $($chain_name:tt)* } = $chain:expr,)?]
+        [@omit_empty_source_chain $($extra:tt)*]
+        $($rest:tt)*
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*]
+            [$({ $($chain_name)* } = ::core::error::Error::source($crate::__log_error_as_error!($err)).is_some().then(|| $chain),)?]
+            [$($extra)*]
+            $($rest)*
         )
     );
-    ($err:expr, %$($k:ident).+, $($field:tt)*) => (
-        $crate::log_error!(
-            $err,
-            level: ::tracing::Level::ERROR,
-            $($field)*
+    // `verbose_at_error_only: true`: the level is now known at compile-time, the
+    // details and the source chain are only computed if it's `ERROR`.
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$lvl:path] [$($target:tt)*] [$($parent:tt)*] [$($message:tt)*]
+        [$({ $($details_name:tt)* } = $details:expr,)*]
+        [$({ $($chain_name:tt)* } = $chain:expr,)?]
+        [@verbose_at_error_only $($extra:tt)*]
+        $($rest:tt)*
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$lvl] [$($target)*] [$($parent)*] [$($message)*]
+            [$({ $($details_name)* } = ($lvl == ::tracing::Level::ERROR).then(|| $details),)*]
+            [$({ $($chain_name)* } = ($lvl == ::tracing::Level::ERROR).then(|| $chain),)?]
+            [$($extra)*]
+            $($rest)*
         )
     );
-    ($err:expr, ?$($k:ident).+) => (
-        $crate::log_error!($err, level: ::tracing::Level::ERROR, ?$($k).+)
+    // The event is emitted, then the hook registered via `set_on_error` (if any) is invoked.
+    // Other markers are pushed in front of `@on_error`: they've been handled by now.
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$lvl:path] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [@on_error $($extra:tt)*]
+        $($rest:tt)*
+    ) => ({
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$lvl] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*] [$($extra)*]
+            $($rest)*
+        );
+        $crate::__log_error_on_error!($err, $lvl);
+    });
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        { $($custom:tt)* }
+    ) => (
+        ::tracing::event!(
+            $($target)* $($parent)* $($lvl)+,
+            $($message)* $($details)* $($chain)* $($extra)* $($custom)*
+        )
     );
-    ($err:expr, %$($k:ident).+) => (
-        $crate::log_error!($lvl, level: ::tracing::Level::ERROR, %$($k).+)
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        { $($custom:tt)* }, $($arg:tt)+
+    ) => (
+        ::tracing::event!(
+            $($target)* $($parent)* $($lvl)+,
+            { $($message)* $($details)* $($chain)* $($extra)* $($custom)* },
+            $($arg)+
+        )
     );
-    ($err:expr, $($k:ident).+) => (
-        $crate::log_error!($err, level: ::tracing::Level::ERROR, $($k).+)
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        $($rest:tt)*
+    ) => (
+        ::tracing::event!(
+            $($target)* $($parent)* $($lvl)+,
+            $($message)* $($details)* $($chain)* $($extra)* $($rest)*
+        )
     );
-    ($err:expr, $($arg:tt)*) => (
-        $crate::log_error!($err, level: ::tracing::Level::ERROR, $($arg)*)
+}
+
+/// Invokes the hook registered via [`set_on_error`], if any.
+///
+/// Not part of the public API.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_on_error {
+    ($err:expr, $lvl:expr) => {
+        // Like the event itself, the hook is compiled out below the static level filter.
+        // The error expression is left alone unless there is a hook to invoke.
+        if $lvl <= ::tracing::level_filters::STATIC_MAX_LEVEL && $crate::__has_on_error() {
+            match &$err {
+                e => $crate::__on_error(
+                    $crate::fields::error_type_name(e),
+                    $crate::__log_error_as_error!(*e),
+                    $lvl,
+                ),
+            }
+        }
+    };
+}
+
+/// There is no hook to invoke without the `std` feature.
+///
+/// Not part of the public API.
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_on_error {
+    ($err:expr, $lvl:expr) => {
+        ()
+    };
+}
+
+/// Resolves the name of an error field, given the (optional) prefix chosen via
+/// [`log_error_with_prefix!`].
+///
+/// Not part of the public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_field_name {
+    ([] ERROR_SOURCES_INDEXED $i:tt) => {
+        $crate::fields::ERROR_SOURCES_INDEXED[$i]
+    };
+    ([$prefix:literal] ERROR_SOURCES_INDEXED $i:tt) => {
+        ::core::concat!($prefix, ".source_", $i)
+    };
+    ([] $name:ident) => {
+        $crate::fields::$name
+    };
+    ([$prefix:literal] ERROR_MESSAGE) => {
+        ::core::concat!($prefix, ".message")
+    };
+    ([$prefix:literal] ERROR_DETAILS) => {
+        ::core::concat!($prefix, ".details")
+    };
+    ([$prefix:literal] ERROR_SOURCE_CHAIN) => {
+        ::core::concat!($prefix, ".source_chain")
+    };
+    ([$prefix:literal] ERROR_TYPE) => {
+        ::core::concat!($prefix, ".type")
+    };
+    ([$prefix:literal] ERROR_BACKTRACE) => {
+        ::core::concat!($prefix, ".backtrace")
+    };
+    ([$prefix:literal] ERROR_ROOT_CAUSE) => {
+        ::core::concat!($prefix, ".root_cause")
+    };
+    ([$prefix:literal] ERROR_CONTEXT) => {
+        ::core::concat!($prefix, ".context")
+    };
+    ([$prefix:literal] ERROR_SUMMARY) => {
+        ::core::concat!($prefix, ".summary")
+    };
+    ([$prefix:literal] ERROR_CHAIN_DEPTH) => {
+        ::core::concat!($prefix, ".chain_depth")
+    };
+    ([$prefix:literal] ERROR_FINGERPRINT) => {
+        ::core::concat!($prefix, ".fingerprint")
+    };
+    ([$prefix:literal] ERROR_DURATION_MS) => {
+        ::core::concat!($prefix, ".duration_ms")
+    };
+    ([$prefix:literal] ERROR_DETAILS_TRUNCATED) => {
+        ::core::concat!($prefix, ".details_truncated")
+    };
+    ([$prefix:literal] ERROR_CATEGORY) => {
+        ::core::concat!($prefix, ".category")
+    };
+    ([$prefix:literal] ERROR_VARIANT) => {
+        ::core::concat!($prefix, ".variant")
+    };
+    ([$prefix:literal] ERROR_RETRYABLE) => {
+        ::core::concat!($prefix, ".retryable")
+    };
+    ([$prefix:literal] ERROR_OS_CODE) => {
+        ::core::concat!($prefix, ".os_code")
+    };
+    ([$prefix:literal] ERROR_JSON) => {
+        $prefix
+    };
+    // Not error fields, they are not affected by the prefix
+    ([$prefix:literal] CODE_FILEPATH) => {
+        $crate::fields::CODE_FILEPATH
+    };
+    ([$prefix:literal] CODE_LINENO) => {
+        $crate::fields::CODE_LINENO
+    };
+    ([$prefix:literal] CODE_COLUMN) => {
+        $crate::fields::CODE_COLUMN
+    };
+}
+
+/// Convert the error passed to [`log_error!`] into something that implements
+/// [`std::error::Error`], see [`as_error`](crate::as_error).
+///
+/// Not part of the public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_as_error {
+    ($err:expr) => {
+        $crate::__log_error_as_error!(@call $err, __log_error_as_error)
+    };
+    (@call $err:expr, $method:ident) => {{
+        #[allow(unused_imports)]
+        use $crate::as_error::*;
+        match &$err {
+            e => (&$crate::as_error::Wrap(e)).__log_error_kind().$method(e),
+        }
+    }};
+    (@retryable $err:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::as_error::*;
+        match &$err {
+            e => (&$crate::as_error::Wrap(e))
+                .__log_error_classification_kind()
+                .__log_error_retryable(e),
+        }
+    }};
+    (@os_code $err:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::as_error::*;
+        match &$err {
+            e => (&$crate::as_error::Wrap(e)).__log_error_os_code(),
+        }
+    }};
+    (@failure_details $err:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::as_error::*;
+        match &$err {
+            e => (&$crate::as_error::Wrap(e))
+                .__log_failure_kind()
+                .__log_failure_details(e),
+        }
+    }};
+}
+
+/// Handles `backtrace: true` for [`log_error!`].
+///
+/// With the `otel` feature enabled, the backtrace and the source chain share the same
+/// field name, so the backtrace replaces the source chain rather than being added
+/// alongside it.
+///
+/// Not part of the public API.
+#[cfg(not(feature = "otel"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_backtrace {
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        $($rest:tt)*
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*] [$($chain)*]
+            [
+                $($extra)*
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_BACKTRACE) } = $crate::__log_error_as_error!(@call $err, __log_error_backtrace),
+            ]
+            $($rest)*
+        )
     );
-    ($err:expr) => (
-        $crate::log_error!($err,)
+}
+
+/// Handles `backtrace: true` for [`log_error!`].
+///
+/// With the `otel` feature enabled, the backtrace and the source chain share the same
+/// field name, so the backtrace replaces the source chain rather than being added
+/// alongside it.
+///
+/// Not part of the public API.
+#[cfg(feature = "otel")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_backtrace {
+    (
+        @parse [$err:expr] [$($prefix:tt)*] [$($lvl:tt)+] [$($target:tt)*] [$($parent:tt)*]
+        [$($message:tt)*] [$($details:tt)*] [$($chain:tt)*] [$($extra:tt)*]
+        $($rest:tt)*
+    ) => (
+        $crate::__log_error!(
+            @parse [$err] [$($prefix)*] [$($lvl)+] [$($target)*] [$($parent)*] [$($message)*] [$($details)*]
+            [
+                { $crate::__log_error_field_name!([$($prefix)*] ERROR_BACKTRACE) } = $crate::__log_error_as_error!(@call $err, __log_error_backtrace_or_source_chain),
+            ]
+            [$($extra)*]
+            $($rest)*
+        )
     );
 }
 
+/// Handles `provided: <type> as <field name>` for [`log_error!`].
+///
+/// Not part of the public API.
+#[cfg(feature = "nightly")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_provided {
+    ($ty:ty, $err:expr) => {
+        $crate::fields::error_provided::<$ty, _>($err).map(::tracing::field::debug)
+    };
+}
+
+/// Handles `provided: <type> as <field name>` for [`log_error!`].
+///
+/// Not part of the public API.
+#[cfg(not(feature = "nightly"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_error_provided {
+    ($ty:ty, $err:expr) => {
+        ::core::compile_error!("`provided:` requires the `nightly` feature of `tracing_log_error`")
+    };
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::testing::{with_captured, FieldVisitor};
+
+    /// An event, as seen by [`Capture`].
+    ///
+    /// Unlike [`crate::testing::CapturedEvent`], it keeps track of the target and
+    /// the parent of the event.
+    #[derive(Clone)]
+    #[cfg_attr(
+        all(feature = "disable-in-release", not(debug_assertions)),
+        allow(dead_code)
+    )]
+    struct CapturedEvent {
+        level: tracing::Level,
+        target: String,
+        /// The explicit parent of the event, if any.
+        parent: Option<Id>,
+        /// The recorded fields, keyed by field name.
+        fields: HashMap<&'static str, String>,
+    }
+
+    /// A subscriber that keeps track of the events it receives.
+    #[derive(Default)]
+    struct Capture {
+        events: Arc<Mutex<Vec<CapturedEvent>>>,
+        next_span_id: AtomicU64,
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(self.next_span_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
 
-    use super::log_error;
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = HashMap::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            self.events.lock().unwrap().push(CapturedEvent {
+                level: *event.metadata().level(),
+                target: event.metadata().target().to_owned(),
+                parent: event.parent().cloned(),
+                fields,
+            });
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    /// Run `f` and return all the events it emitted.
+    fn capture(f: impl FnOnce()) -> Vec<CapturedEvent> {
+        let subscriber = Capture::default();
+        let events = subscriber.events.clone();
+        tracing::subscriber::with_default(subscriber, f);
+        let events = events.lock().unwrap();
+        events.clone()
+    }
 
     #[test]
+    #[allow(clippy::io_other_error)]
     fn my_test() {
         let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
         // Most common usage
@@ -286,6 +2678,10 @@ mod tests {
         let a = PathBuf::from("a path");
         let b = "A string".to_string();
         log_error!(e, custom_field = ?a, custom_field2 = %b, ?a, %b, "Hello");
+        // A single field using the % and ? shorthands
+        log_error!(e, %b);
+        log_error!(e, ?a);
+        log_error!(e, level: tracing::Level::WARN, %b);
         // Using {{ }} to log fields using a constant as their name
         const FIELD: &str = "field";
         log_error!(
@@ -297,5 +2693,1412 @@ mod tests {
             } = "value",
             "Yay"
         );
+        // Capturing a backtrace
+        log_error!(e, backtrace: true, "Yay");
+        log_error!(e, backtrace: true);
+        log_error!(e, level: tracing::Level::WARN, backtrace: true, "Yay");
+        log_error!(e, backtrace: true, level: tracing::Level::WARN, custom_field = "value", "Yay");
+        // Root cause
+        log_error!(e, root_cause: true, "Yay");
+        log_error!(*y, level: tracing::Level::WARN, root_cause: true);
+        // Breadcrumbs
+        log_error!(e, context: &["POST /users", "tenant=acme"], "Yay");
+        log_error!(*y, context: &Vec::<String>::new());
+        // Summary
+        log_error!(e, summary: true, "Yay");
+        log_error!(*y, summary: false);
+        // Enum variant
+        log_error!(e, variant: true, "Yay");
+        log_error!(*y, variant: false);
+        // Fingerprint
+        log_error!(e, fingerprint: true, "Yay");
+        log_error!(*y, fingerprint: false, root_cause: true);
+        // Chain depth
+        log_error!(e, chain_depth: true, "Yay");
+        log_error!(*y, level: tracing::Level::WARN, chain_depth: true, root_cause: true);
+        // Indexed sources
+        log_error!(e, indexed_sources: 3, "Yay");
+        log_error!(*y, level: tracing::Level::WARN, indexed_sources: 8);
+        // Capped source chain
+        log_error!(e, max_source_depth: 5, "Yay");
+        log_error!(e, level: tracing::Level::WARN, max_source_depth: 2 + 3);
+        // Deduplicated source chain
+        log_error!(e, dedupe_sources: true, "Yay");
+        // Custom separator for the source chain
+        log_error!(e, source_sep: " -> ", "Yay");
+        log_error!(e, level: tracing::Level::WARN, source_sep: "\t");
+        // Type names in the source chain
+        log_error!(e, with_types: true, "Yay");
+        log_error!(*y, level: tracing::Level::WARN, with_types: false);
+        // Category
+        log_error!(e, category: "network", "Yay");
+        log_error!(*y, level: tracing::Level::WARN, category: 42);
+        // Truncated message
+        log_error!(e, max_message_len: 512, "Yay");
+        log_error!(e, level: tracing::Level::WARN, max_message_len: 2 * 256);
+        // Truncated details
+        log_error!(e, max_details_len: 2048, "Yay");
+        log_error!(e, verbose_at_error_only: true, level: tracing::Level::WARN, max_details_len: 2 * 1024);
+        // Verbose fields at the `ERROR` level only
+        log_error!(e, verbose_at_error_only: true, "Yay");
+        log_error!(e, verbose_at_error_only: true, level: tracing::Level::WARN, pretty: true);
+        // Omitting an empty source chain
+        log_error!(e, skip_source_chain: true, "Yay");
+        log_error!(e, skip_source_chain: false, omit_empty_source_chain: true);
+        log_error!(e, omit_empty_source_chain: true, "Yay");
+        log_error!(e, omit_empty_source_chain: true, verbose_at_error_only: true, source_chain: structured);
+        // Custom details
+        log_error!(e, details: "Sanitized", "Yay");
+        log_error!(e, details: format!("{}", 42), level: tracing::Level::WARN);
+        // Skipping redundant details
+        log_error!(e, skip_redundant_details: true, "Yay");
+        // Pretty-printed details
+        log_error!(e, pretty: true, "Yay");
+        log_error!(e, level: tracing::Level::WARN, pretty: true);
+        // A single JSON field
+        #[cfg(feature = "json")]
+        log_error!(e, json: true, "Yay");
+        #[cfg(feature = "json")]
+        log_error!(e, level: tracing::Level::WARN, json: true);
+        // Structured source chain
+        log_error!(e, source_chain: structured, "Yay");
+        log_error!(e, level: tracing::Level::WARN, source_chain: structured, backtrace: true);
+        // Pre-built source chain
+        let sources = vec!["Yay".to_string()];
+        log_error!(e, source_chain: &sources, "Yay");
+        log_error!(e, source_chain: ["Yay"], backtrace: true);
+    }
+
+    #[test]
+    fn loggable_error() {
+        // Global fields are only recorded by `LoggableError`: registering one here doesn't
+        // affect the other tests.
+        crate::register_global_field("service.name", "unknown");
+        crate::register_global_field("service.name", "checkout");
+        crate::register_global_field("region", "us");
+        let e = std::io::Error::other("My error");
+        let events = capture(|| {
+            let mut event = crate::LoggableError::new(&e)
+                .level(tracing::Level::WARN)
+                .target("middleware");
+            for (key, value) in [("tenant", "acme"), ("region", "eu"), ("tenant", "globex")] {
+                event = event.field(key, value);
+            }
+            event.field("attempts", 3).emit("Yay");
+            crate::LoggableError::new(&e).emit(format_args!("Here I am, {}", "friend"));
+            let context = HashMap::from([("request_id".to_string(), "42".to_string())]);
+            crate::LoggableError::new(&e)
+                .fields(&context)
+                .field("user_id", "ada")
+                .emit("Yay");
+        });
+        assert_eq!(events.len(), 3);
+        for event in &events {
+            assert_eq!(event.fields["service.name"], "checkout");
+        }
+        assert_eq!(events[0].level, tracing::Level::WARN);
+        assert_eq!(events[0].target, "middleware");
+        assert_eq!(events[0].fields["message"], "Yay");
+        assert_eq!(events[0].fields["tenant"], "globex");
+        assert_eq!(events[0].fields["region"], "eu");
+        assert_eq!(events[0].fields["attempts"], "3");
+        assert_eq!(events[0].fields[crate::fields::ERROR_MESSAGE], "My error");
+        assert_eq!(
+            events[0].fields[crate::fields::ERROR_TYPE],
+            "std::io::error::Error"
+        );
+        assert_eq!(events[1].level, tracing::Level::ERROR);
+        assert_eq!(events[1].fields["message"], "Here I am, friend");
+        assert!(events[1]
+            .fields
+            .contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+        assert_eq!(events[2].fields["request_id"], "42");
+        assert_eq!(events[2].fields["user_id"], "ada");
+    }
+
+    #[test]
+    fn loggable_error_lazy_message() {
+        let e = std::io::Error::other("My error");
+        let invoked = std::cell::Cell::new(0);
+        let message = || {
+            invoked.set(invoked.get() + 1);
+            format!("Failed for {}", "ada")
+        };
+        let events = capture(|| {
+            crate::LoggableError::new(&e).emit_with(message);
+        });
+        assert_eq!(events[0].fields["message"], "Failed for ada");
+        assert_eq!(invoked.get(), 1);
+
+        // Nobody is listening: the closure isn't invoked
+        crate::LoggableError::new(&e).emit_with(message);
+        assert_eq!(invoked.get(), 1);
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn anyhow_error() {
+        use anyhow::Context;
+
+        let e = Err::<(), _>(anyhow::anyhow!("Root"))
+            .context("Outer")
+            .unwrap_err();
+        let events = with_captured(|| {
+            log_error!(e, root_cause: true, "Yay");
+            log_error!(&e, source_chain: structured);
+            log_error!(*e, max_source_depth: 1);
+            log_error!(e, backtrace: true);
+        });
+        assert_eq!(events.len(), 4);
+        for (_, fields) in &events {
+            assert_eq!(fields[crate::fields::ERROR_MESSAGE], "Outer");
+        }
+        assert_eq!(events[0].1[crate::fields::ERROR_SOURCE_CHAIN], "- Root\n");
+        assert_eq!(events[0].1[crate::fields::ERROR_ROOT_CAUSE], "Root");
+        assert_eq!(
+            events[1].1[crate::fields::ERROR_SOURCE_CHAIN],
+            r#"["Root"]"#
+        );
+        assert_eq!(events[2].1[crate::fields::ERROR_SOURCE_CHAIN], "- Root\n");
+        if !cfg!(feature = "otel") {
+            assert_eq!(
+                events[3].1.get(crate::fields::ERROR_BACKTRACE).cloned(),
+                crate::fields::anyhow_backtrace(&e)
+            );
+        }
+    }
+
+    #[test]
+    fn log_errors() {
+        let errors = vec![
+            std::io::Error::other("First"),
+            std::io::Error::other("Second"),
+        ];
+        let events = with_captured(|| {
+            log_errors!(&errors, "Yay");
+            log_errors!(errors.iter(), level: tracing::Level::WARN, custom_field = "value", "Here I am, {}", "friend");
+            log_errors!(&errors);
+            log_errors!(Vec::<std::io::Error>::new(), "Yay");
+        });
+        assert_eq!(events.len(), 3);
+        for (_, fields) in &events {
+            assert_eq!(fields[crate::fields::ERROR_COUNT], "2");
+            assert_eq!(
+                fields[crate::fields::ERRORS],
+                format!("{:?}", crate::fields::error_batch(&errors))
+            );
+        }
+        assert_eq!(events[0].0, tracing::Level::ERROR);
+        assert_eq!(events[0].1["message"], "Yay");
+        assert_eq!(events[1].0, tracing::Level::WARN);
+        assert_eq!(events[1].1["custom_field"], "value");
+        assert_eq!(events[1].1["message"], "Here I am, friend");
+    }
+
+    #[test]
+    fn error_expression_is_evaluated_lazily() {
+        let evaluated = std::cell::Cell::new(0);
+        let make = || {
+            evaluated.set(evaluated.get() + 1);
+            std::io::Error::other("My error")
+        };
+
+        tracing::subscriber::with_default(tracing::subscriber::NoSubscriber::default(), || {
+            log_error!(make(), "Yay");
+            log_error!(make(), level: crate::level_from_config("error").unwrap(), field = 42);
+            log_error!(make(), level: tracing::Level::WARN, root_cause: true);
+            log_error_with_prefix!("err", make());
+        });
+        assert_eq!(evaluated.get(), 0);
+    }
+
+    #[cfg(all(feature = "disable-in-release", not(debug_assertions)))]
+    #[test]
+    fn disabled_in_release() {
+        let evaluated = std::cell::Cell::new(0);
+        let make = || {
+            evaluated.set(evaluated.get() + 1);
+            std::io::Error::other("My error")
+        };
+        let message = "Yay";
+
+        let events = with_captured(|| {
+            log_error!(make(), "{}", message);
+            log_error!(make(), level: tracing::Level::WARN, field = 42);
+            log_error_with_prefix!("err", make());
+            log_error_tiered!(make(), "Yay");
+            log_error_once!(crate::LoggedOnce::new(make()), "Yay");
+            log_error_if!(make(), when: |_| true, "Yay");
+            log_error_throttled!(std::time::Duration::ZERO, make(), "Yay");
+            log_failure!("The job failed", reason = "timeout");
+        });
+        assert!(events.is_empty());
+        assert_eq!(evaluated.get(), 0);
+    }
+
+    #[test]
+    fn error_span() {
+        let _ = capture(|| {
+            let span = error_span!(tracing::Level::INFO, "request", tenant = "acme");
+            let fields = span.metadata().unwrap().fields();
+            for name in [
+                crate::fields::ERROR_MESSAGE,
+                crate::fields::ERROR_DETAILS,
+                crate::fields::ERROR_SOURCE_CHAIN,
+                crate::fields::ERROR_TYPE,
+                "tenant",
+            ] {
+                assert!(fields.field(name).is_some(), "{name} isn't declared");
+            }
+            let _ = error_span!(tracing::Level::WARN, "request");
+        });
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn provided() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct RequestId(u64);
+
+        #[derive(Debug)]
+        struct Timeout(RequestId);
+
+        impl std::fmt::Display for Timeout {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("Timeout")
+            }
+        }
+
+        impl std::error::Error for Timeout {
+            fn provide<'a>(&'a self, request: &mut std::error::Request<'a>) {
+                request.provide_ref::<RequestId>(&self.0);
+            }
+        }
+
+        let e = Timeout(RequestId(42));
+        let events = with_captured(|| {
+            log_error!(e, provided: RequestId as request.id, "Yay");
+            log_error!(e, provided: u64 as missing);
+        });
+        assert_eq!(events[0].1["request.id"], "RequestId(42)");
+        assert_eq!(events[0].1["message"], "Yay");
+        assert!(!events[1].1.contains_key("missing"));
+    }
+
+    #[test]
+    fn log_none() {
+        assert_eq!(log_none!(Some(1), "Missing"), Some(1));
+        assert_eq!(log_none!(None::<u32>, "Missing"), None);
+        let a = "friend";
+        assert_eq!(
+            log_none!(None::<u32>, level: tracing::Level::WARN, custom_field = "value", "Here I am, {}", a),
+            None
+        );
+        assert_eq!(log_none!(None::<u32>, ?a, %a, "Yay"), None);
+    }
+
+    // These tests assert on the emitted events: there are none if error logging is
+    // stripped from the build, see `disable-in-release`.
+    #[cfg(not(all(feature = "disable-in-release", not(debug_assertions))))]
+    mod events {
+        use super::*;
+
+        #[test]
+        fn display_shorthand_field_is_recorded() {
+            let e = std::io::Error::other("My error");
+            let foo = "foo";
+            let events = with_captured(|| {
+                log_error!(e, %foo, bar = 1, "Yay");
+                log_error!(e, level: tracing::Level::WARN, %foo, bar = 1, "Yay");
+            });
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].0, tracing::Level::ERROR);
+            assert_eq!(events[1].0, tracing::Level::WARN);
+            for (_, fields) in events {
+                assert_eq!(fields["foo"], "foo");
+                assert_eq!(fields["bar"], "1");
+                assert_eq!(fields[crate::fields::ERROR_MESSAGE], "My error");
+                assert_eq!(fields[crate::fields::ERROR_DETAILS], format!("{:?}", e));
+                assert_eq!(fields["message"], "Yay");
+            }
+        }
+
+        #[test]
+        fn single_display_shorthand_field_is_recorded() {
+            let e = std::io::Error::other("My error");
+            let foo = "foo";
+            let events = with_captured(|| log_error!(e, %foo));
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].1["foo"], "foo");
+            assert_eq!(events[0].1[crate::fields::ERROR_MESSAGE], "My error");
+        }
+
+        #[test]
+        fn custom_target() {
+            let e = std::io::Error::other("My error");
+            let events = capture(|| {
+                log_error!(e, target: "db::pool", "Yay");
+                log_error!(e, target: "db::pool");
+                log_error!(e, target: "db::pool", level: tracing::Level::WARN, custom_field = "value", "Yay");
+                log_error!(e, level: tracing::Level::WARN, target: "db::pool", %e, "Yay");
+                log_error!(e, target: "db::pool", backtrace: true, "Yay");
+            });
+            assert_eq!(events.len(), 5);
+            for event in &events {
+                assert_eq!(event.target, "db::pool");
+                assert_eq!(event.fields[crate::fields::ERROR_MESSAGE], "My error");
+            }
+            assert_eq!(events[2].level, tracing::Level::WARN);
+            assert_eq!(events[2].fields["custom_field"], "value");
+            assert_eq!(events[3].level, tracing::Level::WARN);
+        }
+
+        #[test]
+        fn explicit_parent() {
+            let e = std::io::Error::other("My error");
+            let events = capture(|| {
+                let span = tracing::info_span!("task");
+                log_error!(e, parent: &span, "Yay");
+                log_error!(e, parent: &span);
+                log_error!(e, parent: &span, level: tracing::Level::WARN, custom_field = "value", "Yay");
+                log_error!(e, level: tracing::Level::WARN, target: "tasks", parent: &span, "Yay");
+                log_error!(e, parent: span.id(), target: "tasks", backtrace: true, "Yay");
+                log_error!(e, parent: None, "Yay");
+            });
+            assert_eq!(events.len(), 6);
+            for event in &events[..5] {
+                assert_eq!(event.parent, Some(Id::from_u64(1)));
+            }
+            assert_eq!(events[2].level, tracing::Level::WARN);
+            assert_eq!(events[3].level, tracing::Level::WARN);
+            assert_eq!(events[3].target, "tasks");
+            assert_eq!(events[4].target, "tasks");
+            assert_eq!(events[5].parent, None);
+        }
+
+        #[test]
+        fn log_error_return() {
+            let mut n_evaluations = 0;
+            let mut make_error = || {
+                n_evaluations += 1;
+                std::io::Error::other("My error")
+            };
+            let events = capture(|| {
+                let e = log_error_return!(make_error(), level: tracing::Level::WARN, custom_field = "value", "Yay");
+                assert_eq!(e.kind(), std::io::ErrorKind::Other);
+                assert_eq!(e.to_string(), "My error");
+                let _ = log_error_return!(std::io::Error::other("My error"));
+            });
+            assert_eq!(n_evaluations, 1);
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].level, tracing::Level::WARN);
+            assert_eq!(events[0].fields["custom_field"], "value");
+            assert_eq!(events[0].fields[crate::fields::ERROR_MESSAGE], "My error");
+        }
+
+        #[test]
+        fn log_map_err() {
+            #[derive(Debug)]
+            struct Wrapped(std::io::Error);
+
+            impl From<std::io::Error> for Wrapped {
+                fn from(e: std::io::Error) -> Self {
+                    Self(e)
+                }
+            }
+
+            let failing = || Err::<(), _>(std::io::Error::other("My error"));
+            let events = capture(|| {
+                let e = failing().map_err(log_map_err!()).unwrap_err();
+                assert_eq!(e.to_string(), "My error");
+                let _ = failing().map_err(log_map_err!(
+                    level: tracing::Level::WARN,
+                    custom_field = "value",
+                    "Yay"
+                ));
+                let e = failing()
+                    .map_err(log_map_err!(into: Wrapped, "Yay"))
+                    .unwrap_err();
+                assert_eq!(e.0.to_string(), "My error");
+                let _ = Ok::<(), std::io::Error>(()).map_err(log_map_err!("Nay"));
+            });
+            assert_eq!(events.len(), 3);
+            assert_eq!(events[0].fields[crate::fields::ERROR_MESSAGE], "My error");
+            assert_eq!(events[1].level, tracing::Level::WARN);
+            assert_eq!(events[1].fields["custom_field"], "value");
+            assert_eq!(events[2].fields["message"], "Yay");
+        }
+
+        #[test]
+        fn log_result() {
+            let events = capture(|| {
+                let outcome = log_result!(Ok::<_, std::io::Error>(1), ok: "Saved", err: "Failed");
+                assert_eq!(outcome.unwrap(), 1);
+                let outcome = log_result!(
+                    Err::<(), _>(std::io::Error::other("My error")),
+                    ok: "Saved",
+                    err: "Failed",
+                    custom_field = "value"
+                );
+                assert_eq!(outcome.unwrap_err().to_string(), "My error");
+                let _ =
+                    log_result!(Ok::<_, std::io::Error>(()), err: "Failed", custom_field = "value");
+            });
+            assert_eq!(events.len(), 3);
+            assert_eq!(events[0].level, tracing::Level::INFO);
+            assert_eq!(events[0].fields["message"], "Saved");
+            assert!(!events[0].fields.contains_key(crate::fields::ERROR_MESSAGE));
+            assert_eq!(events[1].level, tracing::Level::ERROR);
+            assert_eq!(events[1].fields["message"], "Failed");
+            assert_eq!(events[1].fields["custom_field"], "value");
+            assert_eq!(events[1].fields[crate::fields::ERROR_MESSAGE], "My error");
+            assert_eq!(events[2].level, tracing::Level::INFO);
+            assert!(!events[2].fields.contains_key("message"));
+            assert_eq!(events[2].fields["custom_field"], "value");
+        }
+
+        #[test]
+        fn custom_prefix() {
+            let e = std::io::Error::other("My error");
+            let events = capture(|| {
+                log_error_with_prefix!("err", e);
+                log_error_with_prefix!("err", e, level: tracing::Level::WARN, root_cause: true, chain_depth: true, custom_field = "value", "Yay");
+            });
+            assert_eq!(events.len(), 2);
+            for event in &events {
+                assert_eq!(event.fields["err.message"], "My error");
+                assert!(event.fields.contains_key("err.details"));
+                assert!(event.fields.contains_key("err.source_chain"));
+                assert!(event.fields.contains_key("err.type"));
+                assert!(!event.fields.contains_key(crate::fields::ERROR_MESSAGE));
+            }
+            assert_eq!(events[1].level, tracing::Level::WARN);
+            assert_eq!(events[1].fields["err.root_cause"], "My error");
+            assert_eq!(events[1].fields["err.chain_depth"], "0");
+            assert_eq!(events[1].fields["custom_field"], "value");
+        }
+
+        #[test]
+        fn caller_location() {
+            let e = std::io::Error::other("My error");
+            let events = capture(|| {
+                log_error!(e, caller: true, "Yay");
+                log_error_with_prefix!("err", e, level: tracing::Level::WARN, caller: true);
+            });
+            assert_eq!(events.len(), 2);
+            for event in &events {
+                assert_eq!(event.fields[crate::fields::CODE_FILEPATH], file!());
+                assert!(event.fields.contains_key(crate::fields::CODE_COLUMN));
+            }
+            let line: u32 = events[0].fields[crate::fields::CODE_LINENO]
+                .parse()
+                .unwrap();
+            assert_eq!(
+                line + 1,
+                events[1].fields[crate::fields::CODE_LINENO]
+                    .parse()
+                    .unwrap()
+            );
+        }
+
+        #[test]
+        fn pretty_details() {
+            let e = std::io::Error::other("My error");
+            let events = with_captured(|| {
+                log_error!(e, pretty: true, "Yay");
+                log_error!(e, pretty: true, level: tracing::Level::WARN, pretty: false);
+            });
+            assert_eq!(events.len(), 2);
+            assert_eq!(
+                events[0].1[crate::fields::ERROR_DETAILS],
+                format!("{:#?}", e)
+            );
+            assert_eq!(events[1].0, tracing::Level::WARN);
+            assert_eq!(
+                events[1].1[crate::fields::ERROR_DETAILS],
+                format!("{:?}", e)
+            );
+        }
+
+        #[test]
+        fn log_error_throttled() {
+            let hour = std::time::Duration::from_secs(3600);
+            let events = capture(|| {
+                for interval in [hour, hour, hour, std::time::Duration::ZERO] {
+                    let e = std::io::Error::other("My error");
+                    log_error_throttled!(interval, e, level: tracing::Level::WARN, custom_field = "value", "Yay");
+                }
+                log_error_throttled!(hour, std::io::Error::other("My error"));
+            });
+            assert_eq!(events.len(), 3);
+            assert_eq!(events[0].level, tracing::Level::WARN);
+            assert_eq!(events[0].fields["custom_field"], "value");
+            assert!(!events[0].fields.contains_key("suppressed_count"));
+            assert_eq!(events[1].fields["suppressed_count"], "2");
+            assert_eq!(events[1].fields[crate::fields::ERROR_MESSAGE], "My error");
+            assert_eq!(events[2].level, tracing::Level::ERROR);
+        }
+
+        #[cfg(feature = "json")]
+        #[test]
+        fn json_field() {
+            let e = std::io::Error::other("My error");
+            let events = with_captured(|| {
+                log_error!(e, json: true, "Yay");
+                log_error_with_prefix!("err", e, level: tracing::Level::WARN, json: true);
+                log_error!(e, level: tracing::Level::WARN, json: true, verbose_at_error_only: true);
+            });
+            assert_eq!(events.len(), 3);
+            assert_eq!(
+                events[0].1[crate::fields::ERROR_JSON],
+                crate::fields::error_json(&e)
+            );
+            assert!(events[0].1.contains_key(crate::fields::ERROR_TYPE));
+            assert!(!events[0].1.contains_key(crate::fields::ERROR_MESSAGE));
+            assert!(!events[0].1.contains_key(crate::fields::ERROR_DETAILS));
+            assert!(!events[0].1.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+            assert_eq!(events[1].1["err"], crate::fields::error_json(&e));
+            // `verbose_at_error_only` doesn't apply to the JSON object
+            assert!(events[2].1.contains_key(crate::fields::ERROR_JSON));
+        }
+
+        #[test]
+        fn log_failure() {
+            struct Status;
+
+            impl std::fmt::Display for Status {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("Quota exceeded")
+                }
+            }
+
+            let events = with_captured(|| {
+                log_failure!("Quota exceeded".to_string(), level: tracing::Level::WARN, custom_field = "value", "Yay");
+                log_failure!(Status, caller: true);
+                log_failure!("Quota exceeded", pretty: true, "Here I am, {}", "friend");
+            });
+            assert_eq!(events.len(), 3);
+            for (_, fields) in &events {
+                assert_eq!(fields[crate::fields::ERROR_MESSAGE], "Quota exceeded");
+                assert!(!fields.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+                assert!(!fields.contains_key(crate::fields::ERROR_TYPE));
+            }
+            assert_eq!(events[0].0, tracing::Level::WARN);
+            assert_eq!(
+                events[0].1[crate::fields::ERROR_DETAILS],
+                r#""Quota exceeded""#
+            );
+            assert_eq!(events[0].1["custom_field"], "value");
+            assert!(!events[1].1.contains_key(crate::fields::ERROR_DETAILS));
+            assert!(events[1].1.contains_key(crate::fields::CODE_FILEPATH));
+            assert_eq!(events[2].1["message"], "Here I am, friend");
+        }
+
+        #[test]
+        fn fields_are_computed_lazily() {
+            static N_FORMATTED: std::sync::atomic::AtomicUsize =
+                std::sync::atomic::AtomicUsize::new(0);
+
+            struct Expensive;
+
+            impl std::fmt::Display for Expensive {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    N_FORMATTED.fetch_add(1, Ordering::Relaxed);
+                    f.write_str("Expensive")
+                }
+            }
+
+            impl std::fmt::Debug for Expensive {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    N_FORMATTED.fetch_add(1, Ordering::Relaxed);
+                    f.write_str("Expensive")
+                }
+            }
+
+            impl std::error::Error for Expensive {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    N_FORMATTED.fetch_add(1, Ordering::Relaxed);
+                    None
+                }
+            }
+
+            tracing::subscriber::with_default(tracing::subscriber::NoSubscriber::default(), || {
+                log_error!(Expensive, "Yay");
+                log_error!(Expensive, root_cause: true, chain_depth: true, pretty: true, dedupe_sources: true);
+                log_error!(Expensive, source_chain: structured, backtrace: true);
+                log_failure!(Expensive, "Yay");
+                log_errors!([Expensive, Expensive], "Yay");
+                log_errors!([Expensive], level: tracing::Level::TRACE);
+            });
+            assert_eq!(N_FORMATTED.load(Ordering::Relaxed), 0);
+
+            let events = with_captured(|| log_error!(Expensive, "Yay"));
+            assert_eq!(events.len(), 1);
+            assert!(N_FORMATTED.load(Ordering::Relaxed) > 0);
+        }
+
+        #[test]
+        fn custom_fields_are_computed_lazily() {
+            let evaluated = std::cell::Cell::new(0);
+            let expensive = || {
+                evaluated.set(evaluated.get() + 1);
+                42
+            };
+            let e = std::io::Error::other("My error");
+
+            tracing::subscriber::with_default(tracing::subscriber::NoSubscriber::default(), || {
+                log_error!(e, field = expensive(), "Yay {}", expensive());
+                log_error!(e, level: crate::level_from_config("error").unwrap(), field = expensive());
+                log_error!(e, verbose_at_error_only: true, { field = expensive() }, "Yay");
+                log_error_with_prefix!("err", e, field = expensive());
+                log_error_tiered!(e, field = expensive());
+            });
+            assert_eq!(evaluated.get(), 0);
+
+            let events =
+                with_captured(|| log_error!(e, field = expensive(), "Yay {}", expensive()));
+            assert_eq!(events[0].1["field"], "42");
+            assert_eq!(events[0].1["message"], "Yay 42");
+            assert_eq!(evaluated.get(), 2);
+        }
+
+        #[test]
+        fn static_max_level() {
+            // Run with `--release --features tracing/release_max_level_info` to check the
+            // disabled branch: the other tests expect all levels to be enabled.
+            let evaluated = std::cell::Cell::new(0);
+            let expensive = || {
+                evaluated.set(evaluated.get() + 1);
+                42
+            };
+            let e = std::io::Error::other("My error");
+            let events = with_captured(|| {
+                log_error!(e, level: tracing::Level::DEBUG, field = expensive(), "Yay");
+                log_error!(e, level: crate::level_from_config("debug").unwrap(), field = expensive());
+            });
+
+            let enabled = tracing::Level::DEBUG <= tracing::level_filters::STATIC_MAX_LEVEL;
+            let expected = if enabled { 2 } else { 0 };
+            assert_eq!(events.len(), expected);
+            assert_eq!(evaluated.get(), expected);
+        }
+
+        #[test]
+        fn error_classification() {
+            #[derive(Debug)]
+            struct Timeout;
+
+            impl std::fmt::Display for Timeout {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("Timeout")
+                }
+            }
+
+            impl std::error::Error for Timeout {}
+
+            impl crate::ErrorClassification for Timeout {
+                fn is_retryable(&self) -> Option<bool> {
+                    Some(true)
+                }
+            }
+
+            let boxed: Box<dyn std::error::Error> = Box::new(Timeout);
+            let events = with_captured(|| {
+                log_error!(Timeout, "Yay");
+                log_error!(&Timeout);
+                log_error_with_prefix!("err", Timeout, level: tracing::Level::WARN);
+                log_error!(std::io::Error::other("My error"));
+                log_error!(*boxed);
+            });
+            assert_eq!(events.len(), 5);
+            assert_eq!(events[0].1[crate::fields::ERROR_RETRYABLE], "true");
+            assert_eq!(events[1].1[crate::fields::ERROR_RETRYABLE], "true");
+            assert_eq!(events[2].1["err.retryable"], "true");
+            assert!(!events[3].1.contains_key(crate::fields::ERROR_RETRYABLE));
+            // The classification of the underlying error is lost behind a trait object
+            assert!(!events[4].1.contains_key(crate::fields::ERROR_RETRYABLE));
+        }
+
+        #[test]
+        fn os_code() {
+            let e = std::io::Error::from_raw_os_error(2);
+            let boxed: Box<dyn std::error::Error + Send + Sync> =
+                Box::new(std::io::Error::from_raw_os_error(2));
+            let events = with_captured(|| {
+                log_error!(e, "Yay");
+                log_error!(&e);
+                log_error!(*boxed);
+                log_error_with_prefix!("err", e);
+                log_error!(std::io::Error::other("My error"));
+            });
+            assert_eq!(events.len(), 5);
+            for (_, fields) in &events[..3] {
+                assert_eq!(fields[crate::fields::ERROR_OS_CODE], "2");
+            }
+            assert_eq!(events[3].1["err.os_code"], "2");
+            assert!(!events[4].1.contains_key(crate::fields::ERROR_OS_CODE));
+        }
+
+        #[test]
+        fn indexed_sources() {
+            #[derive(Debug)]
+            struct Wrapper(std::io::Error);
+
+            impl std::fmt::Display for Wrapper {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("Wrapper")
+                }
+            }
+
+            impl std::error::Error for Wrapper {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    Some(&self.0)
+                }
+            }
+
+            let e = Wrapper(std::io::Error::other("My error"));
+            let events = capture(|| {
+                log_error!(e, indexed_sources: 3, "Yay");
+                log_error_with_prefix!("err", e, indexed_sources: 2);
+                log_error!(e);
+            });
+            let source_0 = crate::fields::ERROR_SOURCES_INDEXED[0];
+            let source_1 = crate::fields::ERROR_SOURCES_INDEXED[1];
+            assert_eq!(events[0].fields[source_0], "My error");
+            assert!(!events[0].fields.contains_key(source_1));
+            assert_eq!(events[1].fields["err.source_0"], "My error");
+            assert!(!events[1].fields.contains_key("err.source_1"));
+            assert!(!events[2].fields.contains_key(source_0));
+        }
+
+        #[test]
+        fn truncated_message() {
+            let e = std::io::Error::other("My error");
+            let events = capture(|| {
+                log_error!(e, max_message_len: 4, "Yay");
+                log_error_with_prefix!("err", e, max_message_len: 100);
+            });
+            assert_eq!(events[0].fields[crate::fields::ERROR_MESSAGE], "My e…");
+            assert!(events[0].fields[crate::fields::ERROR_DETAILS].contains("My error"));
+            assert_eq!(events[1].fields["err.message"], "My error");
+        }
+
+        #[test]
+        fn truncated_details() {
+            let e = std::io::Error::other("My error");
+            let evaluated = std::cell::Cell::new(0);
+            let max_len = || {
+                evaluated.set(evaluated.get() + 1);
+                6
+            };
+            let events = capture(|| {
+                log_error!(e, max_details_len: 6, "Yay");
+                log_error_with_prefix!("err", e, max_details_len: 100);
+                log_error!(e, max_details_len: 6, details: "Sanitized");
+                log_error!(e, level: tracing::Level::WARN, max_details_len: 6, verbose_at_error_only: true);
+                log_error!(e, max_details_len: max_len());
+            });
+            // The details are formatted once, for both fields
+            assert_eq!(evaluated.get(), 1);
+            assert_eq!(events[4].fields[crate::fields::ERROR_DETAILS], "Custom…");
+            assert_eq!(events[0].fields[crate::fields::ERROR_DETAILS], "Custom…");
+            assert_eq!(
+                events[0].fields[crate::fields::ERROR_DETAILS_TRUNCATED],
+                "true"
+            );
+            assert_eq!(events[0].fields[crate::fields::ERROR_MESSAGE], "My error");
+            assert_eq!(events[1].fields["err.details"], format!("{:?}", e));
+            assert!(!events[1].fields.contains_key("err.details_truncated"));
+            // The last representation of the details wins
+            assert_eq!(events[2].fields[crate::fields::ERROR_DETAILS], "Sanitized");
+            assert!(!events[2]
+                .fields
+                .contains_key(crate::fields::ERROR_DETAILS_TRUNCATED));
+            assert!(!events[3].fields.contains_key(crate::fields::ERROR_DETAILS));
+            assert!(!events[3]
+                .fields
+                .contains_key(crate::fields::ERROR_DETAILS_TRUNCATED));
+        }
+
+        #[test]
+        fn runtime_level() {
+            let e = std::io::Error::other("My error");
+            let levels = [
+                tracing::Level::ERROR,
+                tracing::Level::WARN,
+                tracing::Level::INFO,
+                tracing::Level::DEBUG,
+                tracing::Level::TRACE,
+            ];
+            let events = capture(|| {
+                for level in levels {
+                    log_error!(e, level: level, custom_field = "value", "Yay");
+                }
+                let transient = true;
+                log_error!(e, level: if transient { tracing::Level::WARN } else { tracing::Level::ERROR });
+                log_error!(e, root_cause: true, level: levels[2], { custom_field = "value" });
+                log_error_with_prefix!("err", e, level: levels[3], target: "custom", "Yay");
+            });
+            assert_eq!(events.len(), 8);
+            for (event, level) in events.iter().zip(levels) {
+                assert_eq!(event.level, level);
+                assert_eq!(event.fields["custom_field"], "value");
+                assert_eq!(event.fields["message"], "Yay");
+            }
+            assert_eq!(events[5].level, tracing::Level::WARN);
+            assert_eq!(events[6].level, tracing::Level::INFO);
+            assert!(events[6]
+                .fields
+                .contains_key(crate::fields::ERROR_ROOT_CAUSE));
+            assert_eq!(events[7].level, tracing::Level::DEBUG);
+            assert_eq!(events[7].target, "custom");
+            assert_eq!(events[7].fields["err.message"], "My error");
+        }
+
+        #[test]
+        fn skip_redundant_details() {
+            #[derive(Debug)]
+            struct Timeout;
+
+            impl std::fmt::Display for Timeout {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("Timeout")
+                }
+            }
+
+            impl std::error::Error for Timeout {}
+
+            let e = std::io::Error::other("My error");
+            let events = capture(|| {
+                log_error!(Timeout, skip_redundant_details: true);
+                log_error!(e, skip_redundant_details: true);
+                log_error!(Timeout);
+            });
+            assert!(!events[0].fields.contains_key(crate::fields::ERROR_DETAILS));
+            assert!(events[1].fields.contains_key(crate::fields::ERROR_DETAILS));
+            assert_eq!(events[2].fields[crate::fields::ERROR_DETAILS], "Timeout");
+        }
+
+        #[test]
+        fn prebuilt_source_chain() {
+            let e = std::io::Error::other("My error");
+            let sources = vec!["First".to_string(), "Second".to_string()];
+            let events = with_captured(|| {
+                log_error!(e, source_chain: &sources, "Yay");
+            });
+            let (_, fields) = &events[0];
+            assert_eq!(
+                fields[crate::fields::ERROR_SOURCE_CHAIN],
+                "- First\n- Second\n"
+            );
+            assert_eq!(fields[crate::fields::ERROR_MESSAGE], "My error");
+            assert_eq!(fields[crate::fields::ERROR_DETAILS], format!("{:?}", e));
+        }
+
+        #[test]
+        fn default_level() {
+            let e = std::io::Error::other("My error");
+            let events = with_captured(|| {
+                log_error!(e, "Yay");
+                log_error_with_prefix!("err", e);
+            });
+            // The default level is never changed in this test suite: changing it would
+            // race with the other tests.
+            assert_eq!(events[0].0, tracing::Level::ERROR);
+            assert_eq!(events[1].0, tracing::Level::ERROR);
+        }
+
+        #[test]
+        fn boxed_errors() {
+            #[derive(Debug)]
+            struct Outer(std::io::Error);
+
+            impl std::fmt::Display for Outer {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("Outer")
+                }
+            }
+
+            impl std::error::Error for Outer {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    Some(&self.0)
+                }
+            }
+
+            let inner = || std::io::Error::other("Inner");
+            let boxed: Box<dyn std::error::Error> = Box::new(Outer(inner()));
+            let boxed_send_sync: Box<dyn std::error::Error + Send + Sync> =
+                Box::new(Outer(inner()));
+            let by_ref: &dyn std::error::Error = &*boxed;
+            let boxed_io: Box<dyn std::error::Error + Send + Sync> =
+                Box::new(std::io::Error::from_raw_os_error(2));
+            let events = with_captured(|| {
+                log_error!(boxed, "Yay");
+                log_error!(&boxed_send_sync, "Yay");
+                log_error!(boxed_send_sync.as_ref(), "Yay");
+                log_error!(by_ref, "Yay");
+                log_error!(boxed_io);
+            });
+            assert_eq!(events.len(), 5);
+            for (_, fields) in &events[..4] {
+                assert_eq!(fields[crate::fields::ERROR_MESSAGE], "Outer");
+                assert_eq!(fields[crate::fields::ERROR_SOURCE_CHAIN], "- Inner\n");
+            }
+            assert_eq!(events[4].1[crate::fields::ERROR_OS_CODE], "2");
+            // The boxes are still around
+            drop((boxed, boxed_send_sync));
+        }
+
+        #[test]
+        fn variant() {
+            #[derive(Debug)]
+            enum MyError {
+                Timeout { seconds: u64 },
+            }
+
+            impl std::fmt::Display for MyError {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match self {
+                        MyError::Timeout { seconds } => write!(f, "Timed out after {}s", seconds),
+                    }
+                }
+            }
+
+            impl std::error::Error for MyError {}
+
+            let e = MyError::Timeout { seconds: 5 };
+            let events = with_captured(|| {
+                log_error!(e, variant: true, "Yay");
+                log_error!(e);
+            });
+            assert_eq!(events[0].1[crate::fields::ERROR_VARIANT], "Timeout");
+            assert!(!events[1].1.contains_key(crate::fields::ERROR_VARIANT));
+        }
+
+        #[test]
+        fn fingerprint() {
+            let e = std::io::Error::other("User 42 not found");
+            let events = with_captured(|| {
+                log_error!(e, fingerprint: true, "Yay");
+                log_error_with_prefix!("err", e, fingerprint: true);
+            });
+            assert_eq!(
+                events[0].1[crate::fields::ERROR_FINGERPRINT],
+                crate::fields::error_fingerprint(&e)
+            );
+            assert_eq!(
+                events[1].1["err.fingerprint"],
+                events[0].1[crate::fields::ERROR_FINGERPRINT]
+            );
+        }
+
+        #[test]
+        fn verbose_at_error_only() {
+            let e = std::io::Error::other("My error");
+            let level = tracing::Level::WARN;
+            let events = with_captured(|| {
+                log_error!(e, verbose_at_error_only: true, "Yay");
+                log_error!(e, verbose_at_error_only: true, level: tracing::Level::WARN, pretty: true);
+                log_error!(e, verbose_at_error_only: true, level: level, source_chain: structured);
+                log_error!(e, verbose_at_error_only: false, level: tracing::Level::INFO);
+            });
+            assert_eq!(events.len(), 4);
+            assert!(events[0].1.contains_key(crate::fields::ERROR_DETAILS));
+            assert!(events[0].1.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+            for (level, fields) in &events[1..3] {
+                assert_eq!(*level, tracing::Level::WARN);
+                assert_eq!(fields[crate::fields::ERROR_MESSAGE], "My error");
+                assert!(!fields.contains_key(crate::fields::ERROR_DETAILS));
+                assert!(!fields.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+            }
+            assert!(events[3].1.contains_key(crate::fields::ERROR_DETAILS));
+        }
+
+        #[test]
+        fn log_error_panic() {
+            let panic_message = |f: fn()| {
+                let panic = std::panic::catch_unwind(f).unwrap_err();
+                panic.downcast::<String>().map(|s| *s).unwrap()
+            };
+            let events = with_captured(|| {
+                let message = panic_message(|| {
+                    let what = "configuration";
+                    log_error_panic!(
+                        std::io::Error::other("My error"),
+                        "The {what} is {}",
+                        "unreadable"
+                    )
+                });
+                assert_eq!(message, "The configuration is unreadable: My error");
+                let message = panic_message(|| log_error_panic!(std::io::Error::other("My error")));
+                assert_eq!(message, "My error");
+            });
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].0, tracing::Level::ERROR);
+            assert_eq!(events[0].1["message"], "The configuration is unreadable");
+            assert_eq!(events[0].1[crate::fields::ERROR_MESSAGE], "My error");
+            assert_eq!(events[1].1[crate::fields::ERROR_MESSAGE], "My error");
+        }
+
+        #[test]
+        fn skip_source_chain() {
+            /// Counts the calls to `Error::source`.
+            #[derive(Debug)]
+            struct Counting<'a>(&'a std::sync::atomic::AtomicUsize);
+
+            impl std::fmt::Display for Counting<'_> {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "Counting")
+                }
+            }
+
+            impl std::error::Error for Counting<'_> {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    None
+                }
+            }
+
+            let calls = std::sync::atomic::AtomicUsize::new(0);
+            let e = Counting(&calls);
+            let events = with_captured(|| {
+                log_error!(e, skip_source_chain: true, "Yay");
+                log_error!(e, omit_empty_source_chain: true, skip_source_chain: true);
+            });
+            for (_, fields) in &events {
+                assert!(!fields.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+                assert_eq!(fields[crate::fields::ERROR_MESSAGE], "Counting");
+            }
+            assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+            // The last representation of the source chain wins
+            let events = with_captured(|| {
+                log_error!(e, skip_source_chain: true, source_chain: structured);
+            });
+            assert_eq!(events[0].1[crate::fields::ERROR_SOURCE_CHAIN], "[]");
+            assert!(calls.load(std::sync::atomic::Ordering::Relaxed) > 0);
+        }
+
+        #[test]
+        fn log_error_to() {
+            let isolated = crate::testing::CapturingSubscriber::new();
+            let dispatch = tracing::Dispatch::new(isolated.clone());
+            let e = std::io::Error::other("My error");
+            let events = with_captured(|| {
+                log_error_to!(&dispatch, e, level: tracing::Level::WARN, custom = 1, "Yay");
+            });
+            assert!(events.is_empty());
+
+            let events = isolated.events();
+            assert_eq!(events.len(), 1);
+            let (level, fields) = &events[0];
+            assert_eq!(*level, tracing::Level::WARN);
+            assert_eq!(fields["message"], "Yay");
+            assert_eq!(fields["custom"], "1");
+            assert_eq!(fields[crate::fields::ERROR_MESSAGE], "My error");
+        }
+
+        #[test]
+        fn with_types() {
+            #[derive(Debug)]
+            struct Outer(std::fmt::Error);
+
+            impl std::fmt::Display for Outer {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "Outer")
+                }
+            }
+
+            impl std::error::Error for Outer {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    Some(&self.0)
+                }
+            }
+
+            let e = Outer(std::fmt::Error);
+            let boxed: Box<dyn std::error::Error> = Box::new(std::fmt::Error);
+            let events = with_captured(|| {
+                log_error!(e, with_types: true, "Yay");
+                log_error!(*boxed, with_types: true);
+            });
+            assert_eq!(
+                events[0].1[crate::fields::ERROR_SOURCE_CHAIN],
+                "- [tracing_log_error::tests::events::with_types::Outer] Outer\n\
+                 - [dyn core::error::Error] an error occurred when formatting an argument\n"
+            );
+            assert_eq!(
+                events[1].1[crate::fields::ERROR_SOURCE_CHAIN],
+                "- [dyn core::error::Error] an error occurred when formatting an argument\n"
+            );
+        }
+
+        #[test]
+        fn omit_empty_source_chain() {
+            #[derive(Debug)]
+            struct Outer(std::io::Error);
+
+            impl std::fmt::Display for Outer {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("Outer")
+                }
+            }
+
+            impl std::error::Error for Outer {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    Some(&self.0)
+                }
+            }
+
+            let e = std::io::Error::other("My error");
+            let outer = Outer(std::io::Error::other("Inner"));
+            let events = with_captured(|| {
+                log_error!(e, omit_empty_source_chain: true, "Yay");
+                log_error!(outer, omit_empty_source_chain: true, "Yay");
+                log_error!(e, source_chain: structured, omit_empty_source_chain: true);
+                log_error!(
+                    outer,
+                    omit_empty_source_chain: true,
+                    level: tracing::Level::WARN,
+                    verbose_at_error_only: true
+                );
+                log_error!(e, omit_empty_source_chain: false);
+            });
+            assert_eq!(events.len(), 5);
+            assert!(!events[0].1.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+            assert_eq!(events[1].1[crate::fields::ERROR_SOURCE_CHAIN], "- Inner\n");
+            assert!(!events[2].1.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+            assert!(!events[3].1.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+            assert_eq!(events[4].1[crate::fields::ERROR_SOURCE_CHAIN], "");
+        }
+
+        #[test]
+        fn custom_details() {
+            let e = std::io::Error::other("My error");
+            let evaluated = std::cell::Cell::new(0);
+            let details = || {
+                evaluated.set(evaluated.get() + 1);
+                "Sanitized"
+            };
+            let events = with_captured(|| {
+                log_error!(e, details: details(), "Yay");
+                log_error!(e, details: details(), level: tracing::Level::TRACE, "Yay");
+            });
+            assert_eq!(events[0].1[crate::fields::ERROR_DETAILS], "Sanitized");
+            assert_eq!(events[0].1[crate::fields::ERROR_MESSAGE], "My error");
+            assert!(events[0].1.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+
+            // Nobody is listening: the override isn't evaluated
+            log_error!(e, details: details(), "Yay");
+            assert_eq!(evaluated.get(), 2);
+        }
+
+        #[test]
+        fn context() {
+            let e = std::io::Error::other("My error");
+            let breadcrumbs = vec!["POST /users".to_string(), "tenant=acme".to_string()];
+            let none: &[&str] = &[];
+            let events = with_captured(|| {
+                log_error!(e, context: &["POST /users"], "Yay");
+                log_error!(e, context: &breadcrumbs, "Yay");
+                log_error!(e, context: none, "Yay");
+                log_error_with_prefix!("err", e, context: &breadcrumbs);
+            });
+            assert_eq!(events[0].1[crate::fields::ERROR_CONTEXT], "POST /users");
+            assert_eq!(
+                events[1].1[crate::fields::ERROR_CONTEXT],
+                "POST /users > tenant=acme"
+            );
+            assert_eq!(events[1].1["message"], "Yay");
+            assert!(!events[2].1.contains_key(crate::fields::ERROR_CONTEXT));
+            assert_eq!(events[3].1["err.context"], "POST /users > tenant=acme");
+        }
+
+        #[test]
+        fn summary() {
+            let e = std::io::Error::other("First line\nSecond line");
+            let events = with_captured(|| {
+                log_error!(e, summary: true, "Yay");
+                log_error_with_prefix!("err", e, summary: true);
+            });
+            assert_eq!(events[0].1[crate::fields::ERROR_SUMMARY], "First line");
+            assert_eq!(
+                events[0].1[crate::fields::ERROR_MESSAGE],
+                "First line\nSecond line"
+            );
+            assert_eq!(events[1].1["err.summary"], "First line");
+        }
+
+        #[test]
+        fn took() {
+            let e = std::io::Error::other("My error");
+            let events = with_captured(|| {
+                log_error!(e, took: std::time::Duration::from_millis(1234), "Yay");
+                log_error_with_prefix!("err", e, took: std::time::Duration::from_secs(2));
+            });
+            assert_eq!(events[0].1[crate::fields::ERROR_DURATION_MS], "1234");
+            assert_eq!(events[1].1["err.duration_ms"], "2000");
+        }
+
+        #[test]
+        fn category() {
+            enum Category {
+                Network,
+            }
+
+            impl std::fmt::Display for Category {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match self {
+                        Category::Network => f.write_str("network"),
+                    }
+                }
+            }
+
+            let e = std::io::Error::other("My error");
+            let events = with_captured(|| {
+                log_error!(e, category: Category::Network, "Yay");
+                log_error_with_prefix!("err", e, category: &Category::Network);
+            });
+            assert_eq!(events[0].1[crate::fields::ERROR_CATEGORY], "network");
+            assert_eq!(events[1].1["err.category"], "network");
+        }
+
+        #[test]
+        fn message_first() {
+            let e = std::io::Error::other("My error");
+            let peer = "127.0.0.1:8080";
+            let events = with_captured(|| {
+                log_error!("Yay"; e);
+                log_error!("Dropped {}", peer; e, level: tracing::Level::WARN, peer = peer);
+            });
+            assert_eq!(events[0].0, tracing::Level::ERROR);
+            assert_eq!(events[0].1["message"], "Yay");
+            assert_eq!(events[0].1[crate::fields::ERROR_MESSAGE], "My error");
+            assert_eq!(events[1].0, tracing::Level::WARN);
+            assert_eq!(events[1].1["message"], "Dropped 127.0.0.1:8080");
+            assert_eq!(events[1].1["peer"], "127.0.0.1:8080");
+        }
+
+        #[test]
+        fn log_error_tiered() {
+            static N_DEBUG_FORMATTED: std::sync::atomic::AtomicUsize =
+                std::sync::atomic::AtomicUsize::new(0);
+
+            struct Top(std::io::Error);
+
+            impl std::fmt::Display for Top {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("top")
+                }
+            }
+
+            impl std::fmt::Debug for Top {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    N_DEBUG_FORMATTED.fetch_add(1, Ordering::Relaxed);
+                    f.write_str("Top")
+                }
+            }
+
+            impl std::error::Error for Top {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    Some(&self.0)
+                }
+            }
+
+            /// A subscriber that's only interested in `ERROR` events, and formats their fields.
+            struct ErrorsOnly;
+
+            impl tracing::Subscriber for ErrorsOnly {
+                fn enabled(&self, metadata: &tracing::Metadata<'_>) -> bool {
+                    *metadata.level() == tracing::Level::ERROR
+                }
+
+                fn new_span(&self, _span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+                    tracing::span::Id::from_u64(1)
+                }
+
+                fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+                fn record_follows_from(
+                    &self,
+                    _span: &tracing::span::Id,
+                    _follows: &tracing::span::Id,
+                ) {
+                }
+
+                fn event(&self, event: &tracing::Event<'_>) {
+                    event.record(&mut crate::testing::FieldVisitor(&mut HashMap::new()));
+                }
+
+                fn enter(&self, _span: &tracing::span::Id) {}
+
+                fn exit(&self, _span: &tracing::span::Id) {}
+            }
+
+            let e = Top(std::io::Error::other("root"));
+            tracing::subscriber::with_default(ErrorsOnly, || log_error_tiered!(e, "Yay"));
+            assert_eq!(N_DEBUG_FORMATTED.load(Ordering::Relaxed), 0);
+
+            let events = with_captured(|| log_error_tiered!(e, custom = 1, "Yay"));
+            assert_eq!(events.len(), 2);
+            let (level, fields) = &events[0];
+            assert_eq!(*level, tracing::Level::ERROR);
+            assert_eq!(fields["message"], "Yay");
+            assert_eq!(fields["custom"], "1");
+            assert_eq!(fields[crate::fields::ERROR_ROOT_CAUSE], "root");
+            assert!(fields.contains_key(crate::fields::ERROR_MESSAGE));
+            assert!(!fields.contains_key(crate::fields::ERROR_DETAILS));
+            assert!(!fields.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+            let (level, fields) = &events[1];
+            assert_eq!(*level, tracing::Level::TRACE);
+            assert_eq!(fields["custom"], "1");
+            assert!(fields.contains_key(crate::fields::ERROR_DETAILS));
+            assert!(fields.contains_key(crate::fields::ERROR_SOURCE_CHAIN));
+        }
+
+        #[test]
+        fn log_error_if() {
+            use std::io::ErrorKind;
+
+            let not_found = std::io::Error::new(ErrorKind::NotFound, "Not found");
+            let denied = std::io::Error::new(ErrorKind::PermissionDenied, "Denied");
+            let is_unexpected = |e: &std::io::Error| e.kind() != ErrorKind::NotFound;
+            let events = with_captured(|| {
+                log_error_if!(not_found, when: is_unexpected, "Yay");
+                log_error_if!(denied, when: is_unexpected, "Yay");
+                log_error_if!(
+                    denied,
+                    when: |e| e.kind() == ErrorKind::PermissionDenied,
+                    level: tracing::Level::WARN,
+                    tenant = "acme"
+                );
+            });
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].1[crate::fields::ERROR_MESSAGE], "Denied");
+            assert_eq!(events[1].0, tracing::Level::WARN);
+            assert_eq!(events[1].1["tenant"], "acme");
+        }
+
+        #[test]
+        fn log_error_once() {
+            let e = crate::LoggedOnce::new(std::io::Error::other("My error"));
+            let events = capture(|| {
+                log_error_once!(e, "First");
+                log_error_once!(e, "Second");
+                log_error_once!(&e, level: tracing::Level::WARN, "Third");
+            });
+            assert!(e.is_logged());
+            assert_eq!(events.len(), 3);
+            assert_eq!(events[0].level, tracing::Level::ERROR);
+            assert!(!events[0].fields.contains_key("already_logged"));
+            assert_eq!(
+                events[0].fields[crate::fields::ERROR_TYPE],
+                "std::io::error::Error"
+            );
+            assert_eq!(events[1].level, tracing::Level::DEBUG);
+            assert_eq!(events[1].fields["already_logged"], "true");
+            assert_eq!(events[2].level, tracing::Level::WARN);
+            assert_eq!(events[2].fields["already_logged"], "true");
+        }
     }
 }
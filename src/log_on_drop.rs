@@ -0,0 +1,132 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// A guard that logs an error when it's dropped, unless it was handled first.
+///
+/// It comes in handy for "log if this wasn't handled" patterns: wrap the error as
+/// soon as it's created and call [`into_inner`](LogErrorOnDrop::into_inner) once it's
+/// been dealt with. If the guard is dropped instead (e.g. because of an early return,
+/// or because the thread is panicking), the error is logged via
+/// [`log_error!`](crate::log_error), with the given message.
+///
+/// ```rust
+/// use tracing_log_error::LogErrorOnDrop;
+///
+/// fn retry_later(e: std::io::Error) { /* ... */ }
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let guard = LogErrorOnDrop::new(e, "The upload failed and was never retried");
+/// if true {
+///     // Handled: nothing is logged
+///     retry_later(guard.into_inner());
+/// }
+/// // Otherwise, the error is logged when `guard` goes out of scope
+/// ```
+///
+/// # Unwind safety
+///
+/// Panicking while the thread is already unwinding aborts the process.
+/// The guard runs the whole emission (computing the fields, as well as the
+/// subscriber's work) within [`std::panic::catch_unwind`]: if anything panics, the
+/// panic is caught and the event is dropped, so the guard never panics on drop.
+/// The panic hook still runs, so the nested panic is reported as usual.
+///
+/// Nothing can be caught if your crate is compiled with `panic = "abort"`.
+///
+/// The event is emitted from within this crate: its target is
+/// `tracing_log_error::log_on_drop`, rather than the module the guard was created in.
+/// Requires the `std` feature.
+#[derive(Debug)]
+pub struct LogErrorOnDrop<E: core::error::Error> {
+    error: Option<E>,
+    message: &'static str,
+}
+
+impl<E: core::error::Error> LogErrorOnDrop<E> {
+    /// Wrap an error, to be logged with the given message unless it's handled.
+    pub const fn new(error: E, message: &'static str) -> Self {
+        Self {
+            error: Some(error),
+            message,
+        }
+    }
+
+    /// A reference to the wrapped error.
+    pub fn get_ref(&self) -> &E {
+        self.error
+            .as_ref()
+            .expect("The error is only taken on drop or by `into_inner`")
+    }
+
+    /// Mark the error as handled and unwrap it: it won't be logged.
+    pub fn into_inner(mut self) -> E {
+        self.error
+            .take()
+            .expect("The error is only taken on drop or by `into_inner`")
+    }
+}
+
+impl<E: core::error::Error> Drop for LogErrorOnDrop<E> {
+    fn drop(&mut self) {
+        if let Some(error) = self.error.take() {
+            let message = self.message;
+            let _ = catch_unwind(AssertUnwindSafe(|| {
+                crate::log_error!(error, "{}", message);
+            }));
+        }
+    }
+}
+
+#[cfg(all(test, not(all(feature = "disable-in-release", not(debug_assertions)))))]
+mod tests {
+    use super::*;
+    use crate::testing::with_captured;
+
+    #[derive(Debug)]
+    struct Panicking;
+
+    impl std::fmt::Display for Panicking {
+        fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            panic!("Display panicked")
+        }
+    }
+
+    impl std::error::Error for Panicking {}
+
+    #[test]
+    fn log_on_drop() {
+        let events = with_captured(|| {
+            let guard = LogErrorOnDrop::new(std::io::Error::other("Handled"), "Yay");
+            assert_eq!(guard.get_ref().to_string(), "Handled");
+            let _ = guard.into_inner();
+            let _guard = LogErrorOnDrop::new(std::io::Error::other("Unhandled"), "Yay");
+        });
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].1["message"], "Yay");
+        assert_eq!(events[0].1[crate::fields::ERROR_MESSAGE], "Unhandled");
+    }
+
+    #[test]
+    fn drop_while_panicking() {
+        let events = with_captured(|| {
+            let outcome = catch_unwind(|| {
+                let _unhandled = LogErrorOnDrop::new(std::io::Error::other("Unhandled"), "Yay");
+                // Its `Display` implementation panics while the thread is unwinding
+                let _panicking = LogErrorOnDrop::new(Panicking, "Yay");
+                panic!("The request handler panicked");
+            });
+            // The original panic, not an abort
+            assert!(outcome.is_err());
+        });
+        // With `catch-format-panics`, the panicking error is logged with a placeholder
+        let expected = if cfg!(feature = "catch-format-panics") {
+            2
+        } else {
+            1
+        };
+        assert_eq!(events.len(), expected);
+        assert_eq!(
+            events[expected - 1].1[crate::fields::ERROR_MESSAGE],
+            "Unhandled"
+        );
+    }
+}
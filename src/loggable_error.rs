@@ -0,0 +1,195 @@
+use std::cell::{Cell, OnceCell};
+use std::fmt::Display;
+
+use tracing::field::Value;
+use tracing::Level;
+
+use crate::fields;
+
+/// A builder to log an error when the set of custom fields is only known at runtime.
+///
+/// Prefer [`log_error!`](crate::log_error) whenever the fields are known at
+/// compile-time: it's cheaper and it supports the full `tracing` syntax.
+/// `LoggableError` is meant for the cases where the macro falls short, e.g. a
+/// generic error-handling middleware:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_log_error::LoggableError;
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let context = vec![("tenant", "acme"), ("region", "eu-west-1")];
+///
+/// let mut event = LoggableError::new(&e).level(Level::WARN);
+/// for (key, value) in context {
+///     event = event.field(key, value);
+/// }
+/// event.emit("The request failed");
+/// ```
+///
+/// The event includes the same error fields recorded by `log_error!`, as well as the
+/// global fields registered via [`register_global_field`](crate::register_global_field).
+///
+/// # Limitations
+///
+/// `tracing` expects the fields of an event to be known at compile-time.
+/// `LoggableError` works around it by creating a new callsite for each distinct
+/// combination of level, target, location and field names it encounters—the
+/// corresponding metadata is never freed.
+/// Don't use data-dependent field names (e.g. user ids) or you'll leak memory.
+///
+/// At most 256 fields are recorded on each event: additional fields are discarded.
+pub struct LoggableError<'a> {
+    error: &'a (dyn std::error::Error + 'a),
+    type_name: &'static str,
+    level: Level,
+    target: &'a str,
+    fields: Vec<(String, Box<dyn Value + 'a>)>,
+}
+
+impl<'a> LoggableError<'a> {
+    /// Start building an event for the given error.
+    ///
+    /// Unless you specify otherwise, the event is emitted at the `ERROR` level,
+    /// under the `tracing_log_error` target.
+    pub fn new<E: std::error::Error + 'a>(e: &'a E) -> Self {
+        Self {
+            error: e,
+            type_name: fields::error_type_name(e),
+            level: Level::ERROR,
+            target: env!("CARGO_PKG_NAME"),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Set the level of the event.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Set the target of the event.
+    pub fn target(mut self, target: &'a str) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Add a custom field to the event.
+    ///
+    /// Use [`tracing::field::display`] or [`tracing::field::debug`] to record values
+    /// that implement `Display` or `Debug`, but not [`Value`].
+    /// If a field with the same name was already added, its value is replaced.
+    pub fn field(mut self, key: impl Into<String>, value: impl Value + 'a) -> Self {
+        self.fields.push((key.into(), Box::new(value)));
+        self
+    }
+
+    /// Add several custom fields to the event, e.g. the request context accumulated
+    /// by your web framework:
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use tracing_log_error::LoggableError;
+    ///
+    /// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+    /// let mut context = HashMap::new();
+    /// context.insert("request_id".to_string(), "42".to_string());
+    /// context.insert("user_id".to_string(), "ada".to_string());
+    ///
+    /// LoggableError::new(&e).fields(&context).emit("The request failed");
+    /// ```
+    ///
+    /// It behaves as calling [`field`](Self::field) for each entry, in iteration order.
+    pub fn fields<I, K, V>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Value + 'a,
+    {
+        for (key, value) in fields {
+            self = self.field(key, value);
+        }
+        self
+    }
+
+    /// Emit the event, with the given message.
+    #[track_caller]
+    pub fn emit(self, message: impl Display) {
+        self.emit_with(|| message)
+    }
+
+    /// Emit the event, with the message returned by the given closure.
+    ///
+    /// It comes in handy when the message is expensive to build:
+    ///
+    /// ```rust
+    /// use tracing_log_error::LoggableError;
+    ///
+    /// # fn expensive() -> String { "ada".into() }
+    /// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+    /// LoggableError::new(&e).emit_with(|| format!("The request failed for {}", expensive()));
+    /// ```
+    ///
+    /// The closure is invoked at most once, the first time a subscriber formats the
+    /// message.
+    /// It's never invoked if the event is filtered out, either because its level is
+    /// disabled or because no subscriber is interested in it.
+    #[track_caller]
+    pub fn emit_with<F, M>(self, message: F)
+    where
+        F: FnOnce() -> M,
+        M: Display,
+    {
+        // Computing the source chain isn't free: skip it if nobody is listening.
+        if !crate::dynamic::level_enabled(self.level) {
+            return;
+        }
+        let location = std::panic::Location::caller();
+        let message = tracing::field::display(LazyMessage {
+            make: Cell::new(Some(message)),
+            message: OnceCell::new(),
+        });
+        let error_message = fields::error_message(self.error);
+        let error_details = fields::error_details(self.error);
+        let error_source_chain = fields::error_source_chain(self.error);
+        let global_fields = crate::global_fields::global_fields();
+        let mut values: Vec<(&str, &dyn Value)> = vec![
+            ("message", &message),
+            (fields::ERROR_MESSAGE, &error_message),
+            (fields::ERROR_DETAILS, &error_details),
+            (fields::ERROR_SOURCE_CHAIN, &error_source_chain),
+            (fields::ERROR_TYPE, &self.type_name),
+        ];
+        values.extend(
+            global_fields
+                .iter()
+                .map(|(k, v)| (k.as_str(), v as &dyn Value)),
+        );
+        values.extend(
+            self.fields
+                .iter()
+                .map(|(k, v)| (k.as_str(), &**v as &dyn Value)),
+        );
+        crate::dynamic::emit(self.level, self.target, location, &values);
+    }
+}
+
+/// Builds the message on first use, via the closure passed to
+/// [`LoggableError::emit_with`].
+struct LazyMessage<F, M> {
+    make: Cell<Option<F>>,
+    message: OnceCell<M>,
+}
+
+impl<F: FnOnce() -> M, M: Display> Display for LazyMessage<F, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = self.message.get_or_init(|| {
+            let make = self
+                .make
+                .take()
+                .expect("The message closure is only taken once");
+            make()
+        });
+        message.fmt(f)
+    }
+}
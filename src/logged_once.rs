@@ -0,0 +1,90 @@
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// An error wrapper that remembers whether it has already been logged.
+///
+/// In layered systems, the same error is often logged at every boundary it crosses,
+/// inflating error counts.
+/// Wrap it in a `LoggedOnce` where it's first created and log it with
+/// [`log_error_once!`](crate::log_error_once): only the first event is emitted at the
+/// `ERROR` level, the following ones are downgraded to `DEBUG` and carry an
+/// `already_logged = true` field.
+///
+/// ```rust
+/// use tracing_log_error::{log_error_once, LoggedOnce};
+///
+/// fn connect() -> Result<(), LoggedOnce<std::io::Error>> {
+///     let e = LoggedOnce::new(std::io::Error::new(std::io::ErrorKind::Other, "My error"));
+///     // Emitted at the `ERROR` level
+///     log_error_once!(e, "The connection was dropped");
+///     Err(e)
+/// }
+///
+/// if let Err(e) = connect() {
+///     // Emitted at the `DEBUG` level, with `already_logged = true`
+///     log_error_once!(e, "The request failed");
+/// }
+/// ```
+///
+/// `LoggedOnce` is transparent: its `Display`, `Debug` and `source` are the ones of the
+/// wrapped error, so it can keep propagating on its way up.
+pub struct LoggedOnce<E> {
+    error: E,
+    logged: AtomicBool,
+}
+
+impl<E> LoggedOnce<E> {
+    /// Wrap an error that hasn't been logged yet.
+    pub const fn new(error: E) -> Self {
+        Self {
+            error,
+            logged: AtomicBool::new(false),
+        }
+    }
+
+    /// A reference to the wrapped error.
+    pub fn get_ref(&self) -> &E {
+        &self.error
+    }
+
+    /// Unwrap the error.
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+
+    /// `true` if the error has already been logged.
+    pub fn is_logged(&self) -> bool {
+        self.logged.load(Ordering::Relaxed)
+    }
+
+    /// Mark the error as logged.
+    ///
+    /// It returns `true` if the error had already been marked as logged before.
+    pub fn mark_logged(&self) -> bool {
+        self.logged.swap(true, Ordering::Relaxed)
+    }
+}
+
+impl<E> From<E> for LoggedOnce<E> {
+    fn from(error: E) -> Self {
+        Self::new(error)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for LoggedOnce<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for LoggedOnce<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<E: core::error::Error> core::error::Error for LoggedOnce<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        self.error.source()
+    }
+}
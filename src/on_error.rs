@@ -0,0 +1,77 @@
+use std::sync::OnceLock;
+
+use tracing::Level;
+
+use crate::ErrorReport;
+
+/// A function invoked for every error logged by [`log_error!`](crate::log_error),
+/// see [`set_on_error`].
+pub type OnError = Box<dyn Fn(&ErrorReport, Level) + Send + Sync>;
+
+static ON_ERROR: OnceLock<OnError> = OnceLock::new();
+
+/// Register a function to be invoked for every error logged by [`log_error!`](crate::log_error),
+/// e.g. to increment a counter or to notify a watchdog.
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use tracing_log_error::{log_error, log_error_tiered, set_on_error, ErrorReport};
+///
+/// static N_ERRORS: AtomicU64 = AtomicU64::new(0);
+///
+/// let hook = Box::new(|report: &ErrorReport, level| {
+///     N_ERRORS.fetch_add(1, Ordering::Relaxed);
+///     eprintln!("[{}] {}", level, report.message());
+/// });
+/// assert!(set_on_error(hook).is_ok());
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// log_error!(e, "The connection was dropped");
+/// // Once for the `ERROR` event, not for the `TRACE` one
+/// log_error_tiered!(e, "The connection was dropped");
+/// # if cfg!(not(feature = "disable-in-release")) {
+/// assert_eq!(N_ERRORS.load(Ordering::Relaxed), 2);
+/// # }
+/// ```
+///
+/// The hook is invoked after the event is emitted, with a snapshot of the error fields
+/// and the level of the event.
+/// It's invoked even if the event is filtered out by the subscriber, but not if
+/// the event is filtered out at compile-time, by `tracing`'s `max_level_*` features, or if
+/// error logging is stripped from the build (see the `disable-in-release` feature).
+/// The snapshot is only computed, and the error expression passed to `log_error!` only
+/// evaluated for it, if a hook is registered.
+///
+/// The hook runs at the call site of `log_error!`: it must be cheap and it must not
+/// panic, the panic would propagate to the code that's logging the error.
+/// It must not log errors via `log_error!` either, it would be invoked again.
+///
+/// It's invoked by `log_error!` and by the macros built on top of it (e.g.
+/// [`log_error_tiered!`](crate::log_error_tiered), once per error), except for
+/// [`log_failure!`](crate::log_failure): failures aren't errors.
+///
+/// The hook can only be set once: subsequent calls return the hook you passed in
+/// as an error.
+/// Requires the `std` feature.
+pub fn set_on_error(hook: OnError) -> Result<(), OnError> {
+    ON_ERROR.set(hook)
+}
+
+/// Used by `log_error!` to leave the error alone when there is no hook to invoke.
+///
+/// Not part of the public API.
+#[doc(hidden)]
+pub fn __has_on_error() -> bool {
+    ON_ERROR.get().is_some()
+}
+
+/// Used by `log_error!`, where the type name comes from the expression passed to the
+/// macro rather than from the `dyn Error` it's converted to.
+///
+/// Not part of the public API.
+#[doc(hidden)]
+pub fn __on_error(type_name: &'static str, e: &(dyn core::error::Error + '_), level: Level) {
+    if let Some(hook) = ON_ERROR.get() {
+        hook(&ErrorReport::with_type_name(e, type_name), level);
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::sync::{MutexGuard, PoisonError, RwLockReadGuard, RwLockWriteGuard};
+
+/// A loggable summary of a [`PoisonError`], i.e. a lock whose holder panicked.
+///
+/// `PoisonError` owns the lock guard, which makes it awkward to propagate, so it's often
+/// mapped away with no trace of what happened.
+/// Convert it into a `PoisonedLock` to keep track of the kind of lock and of the type of
+/// the data it guards:
+///
+/// ```rust
+/// use std::sync::Mutex;
+/// use tracing_log_error::{log_error, PoisonedLock};
+///
+/// let cache: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+/// if let Err(e) = cache.lock() {
+///     // Records `error.message = "mutex poisoned"`, and the guarded type
+///     // (`alloc::vec::Vec<u64>`) in `error.details`
+///     log_error!(PoisonedLock::from(&e), "The cache is unavailable");
+/// };
+/// ```
+///
+/// Check out [`log_poison!`](crate::log_poison) to log poisoning inline.
+/// Requires the `std` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoisonedLock {
+    lock: &'static str,
+    data: &'static str,
+}
+
+impl PoisonedLock {
+    /// The kind of lock that was poisoned, i.e. `mutex` or `rwlock`.
+    pub fn lock(&self) -> &'static str {
+        self.lock
+    }
+
+    /// The type of the data guarded by the lock, as reported by [`std::any::type_name`].
+    pub fn data_type_name(&self) -> &'static str {
+        self.data
+    }
+}
+
+macro_rules! impl_from_poison_error {
+    ($($guard:ident => $lock:literal),*) => {
+        $(
+            impl<T: ?Sized> From<&PoisonError<$guard<'_, T>>> for PoisonedLock {
+                fn from(_e: &PoisonError<$guard<'_, T>>) -> Self {
+                    Self {
+                        lock: $lock,
+                        data: std::any::type_name::<T>(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_poison_error!(
+    MutexGuard => "mutex",
+    RwLockReadGuard => "rwlock",
+    RwLockWriteGuard => "rwlock"
+);
+
+impl fmt::Display for PoisonedLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} poisoned", self.lock)
+    }
+}
+
+impl std::error::Error for PoisonedLock {}
+
+#[cfg(all(test, not(all(feature = "disable-in-release", not(debug_assertions)))))]
+mod tests {
+    use std::sync::{Mutex, RwLock};
+
+    use super::*;
+    use crate::fields;
+    use crate::testing::with_captured;
+
+    /// Run the closure on another thread, where it's expected to panic.
+    fn poison(f: impl FnOnce() + Send) {
+        std::thread::scope(|s| {
+            let _ = s.spawn(f).join();
+        });
+    }
+
+    #[test]
+    fn poisoned_lock() {
+        let mutex = Mutex::new(vec![1u64]);
+        let rwlock = RwLock::new(0u8);
+        poison(|| {
+            let _guard = mutex.lock();
+            panic!("Poisoning the mutex");
+        });
+        poison(|| {
+            let _guard = rwlock.write();
+            panic!("Poisoning the lock");
+        });
+
+        let from_mutex = PoisonedLock::from(&mutex.lock().unwrap_err());
+        assert_eq!(from_mutex.to_string(), "mutex poisoned");
+        assert_eq!(from_mutex.data_type_name(), "alloc::vec::Vec<u64>");
+        let from_read = PoisonedLock::from(&rwlock.read().unwrap_err());
+        assert_eq!(from_read.to_string(), "rwlock poisoned");
+        assert_eq!(from_read.data_type_name(), "u8");
+        assert_eq!(from_read, PoisonedLock::from(&rwlock.write().unwrap_err()));
+
+        let healthy = Mutex::new(());
+        let events = with_captured(|| {
+            let guard = crate::log_poison!(mutex.lock(), "The cache is unavailable");
+            assert!(guard.is_err());
+            let guard = crate::log_poison!(healthy.lock(), "Unreachable");
+            assert!(guard.is_ok());
+        });
+        assert_eq!(events.len(), 1);
+        let fields = &events[0].1;
+        assert_eq!(fields["message"], "The cache is unavailable");
+        assert_eq!(fields[fields::ERROR_MESSAGE], "mutex poisoned");
+        assert!(fields[fields::ERROR_DETAILS].contains("alloc::vec::Vec<u64>"));
+    }
+}
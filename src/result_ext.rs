@@ -0,0 +1,53 @@
+use tracing::Level;
+
+/// An extension trait to log errors inline, as they propagate through `?`.
+///
+/// ```rust
+/// use tracing_log_error::ResultExt;
+///
+/// fn read_config() -> Result<String, std::io::Error> {
+///     let config = std::fs::read_to_string("config.toml").log_err("Failed to read the config")?;
+///     Ok(config)
+/// }
+/// ```
+///
+/// The error is logged via [`log_error!`](crate::log_error), so you get the same
+/// fields you'd get by invoking the macro directly.
+/// The event is emitted from within this crate though: its target is
+/// `tracing_log_error::result_ext`, rather than the module you called `log_err` from.
+///
+/// # Errors that don't implement the `Error` trait
+///
+/// The trait is only implemented for `Result`s whose error type implements
+/// [`std::error::Error`]. For error reports such as `anyhow::Error`, which
+/// dereference to a type that implements `Error`, you can borrow the dereferenced
+/// error:
+///
+/// ```rust
+/// use tracing_log_error::ResultExt;
+///
+/// let result: Result<(), anyhow::Error> = Err(anyhow::anyhow!("Hey"));
+/// let _ = result.as_ref().map_err(|e| &**e).log_err("An error occurred");
+/// ```
+pub trait ResultExt<T, E> {
+    /// Log the error, if there is one, at the `ERROR` level.
+    /// The result is returned unchanged.
+    fn log_err(self, msg: &str) -> Result<T, E>;
+
+    /// Log the error, if there is one, at the specified level.
+    /// The result is returned unchanged.
+    fn log_err_at(self, level: Level, msg: &str) -> Result<T, E>;
+}
+
+impl<T, E: core::error::Error> ResultExt<T, E> for Result<T, E> {
+    fn log_err(self, msg: &str) -> Result<T, E> {
+        self.log_err_at(Level::ERROR, msg)
+    }
+
+    fn log_err_at(self, level: Level, msg: &str) -> Result<T, E> {
+        if let Err(e) = &self {
+            crate::log_error!(e, level: level, "{}", msg);
+        }
+        self
+    }
+}
@@ -0,0 +1,44 @@
+use tracing::Level;
+
+/// Pick the level to log an error at, given the HTTP status code it resulted in.
+///
+/// ```rust
+/// use tracing_log_error::{level_for_status, log_error};
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// let status: u16 = 503;
+/// log_error!(e, level: level_for_status(status), status, "The request failed");
+/// ```
+///
+/// Server errors are worth looking into, client errors usually aren't:
+///
+/// - `5xx` maps to `ERROR`.
+/// - `4xx` maps to `WARN`.
+/// - `1xx`, `2xx` and `3xx` map to `INFO`.
+/// - Anything outside of the `100..=599` range isn't a valid status code and maps to `ERROR`.
+///
+/// If you're using the `http` crate, pass `StatusCode::as_u16()`.
+pub fn level_for_status(status: u16) -> Level {
+    match status {
+        100..=399 => Level::INFO,
+        400..=499 => Level::WARN,
+        _ => Level::ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapping() {
+        assert_eq!(level_for_status(200), Level::INFO);
+        assert_eq!(level_for_status(302), Level::INFO);
+        assert_eq!(level_for_status(404), Level::WARN);
+        assert_eq!(level_for_status(499), Level::WARN);
+        assert_eq!(level_for_status(500), Level::ERROR);
+        assert_eq!(level_for_status(599), Level::ERROR);
+        assert_eq!(level_for_status(42), Level::ERROR);
+        assert_eq!(level_for_status(600), Level::ERROR);
+    }
+}
@@ -0,0 +1,107 @@
+//! Helpers to check, in your own tests, which fields were recorded by [`log_error!`].
+//!
+//! Requires the `testing` feature.
+//!
+//! ```rust
+//! use tracing_log_error::{fields, log_error, testing::with_captured};
+//!
+//! let e = std::io::Error::other("My error");
+//! let events = with_captured(|| {
+//!     log_error!(e, tenant = "acme", "The connection was dropped");
+//! });
+//!
+//! assert_eq!(events.len(), 1);
+//! let (level, fields) = &events[0];
+//! assert_eq!(*level, tracing::Level::ERROR);
+//! assert_eq!(fields["message"], "The connection was dropped");
+//! assert_eq!(fields[fields::ERROR_MESSAGE], "My error");
+//! assert!(fields.contains_key(fields::ERROR_DETAILS));
+//! assert_eq!(fields["tenant"], "acme");
+//! ```
+//!
+//! [`log_error!`]: crate::log_error
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// An event, as seen by [`CapturingSubscriber`]: its level and its fields.
+///
+/// Field values are recorded using their `Display` representation for strings and
+/// their `Debug` representation for everything else.
+pub type CapturedEvent = (Level, HashMap<&'static str, String>);
+
+/// A `tracing` subscriber that keeps track of all the events it receives.
+///
+/// The subscriber is cheap to clone: all clones share the same list of captured events.
+#[derive(Clone, Default)]
+pub struct CapturingSubscriber {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+    next_span_id: Arc<AtomicU64>,
+}
+
+impl CapturingSubscriber {
+    /// Create a new subscriber, with no captured events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The events captured so far, in the order they were emitted.
+    pub fn events(&self) -> Vec<CapturedEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl Subscriber for CapturingSubscriber {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = HashMap::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((*event.metadata().level(), fields));
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Run `f` with a [`CapturingSubscriber`] as the default subscriber and return
+/// all the events it emitted.
+pub fn with_captured(f: impl FnOnce()) -> Vec<CapturedEvent> {
+    let subscriber = CapturingSubscriber::new();
+    tracing::subscriber::with_default(subscriber.clone(), f);
+    subscriber.events()
+}
+
+/// Collects the fields of an event into a map, keyed by field name.
+pub(crate) struct FieldVisitor<'a>(pub(crate) &'a mut HashMap<&'static str, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_owned());
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Lets through at most one event per interval, keeping count of the ones it held back.
+///
+/// It's the state behind [`log_error_throttled!`](crate::log_error_throttled), which
+/// creates one `static` `Throttle` per call site.
+/// You can use it directly if you need to share the same throttle across several
+/// call sites:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use tracing_log_error::{log_error, Throttle};
+///
+/// static THROTTLE: Throttle = Throttle::new();
+///
+/// let e = std::io::Error::new(std::io::ErrorKind::Other, "My error");
+/// if let Some(suppressed) = THROTTLE.check(Duration::from_secs(1)) {
+///     log_error!(e, suppressed, "The connection was dropped");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Throttle {
+    /// When an event was last let through, in nanoseconds since [`epoch`].
+    /// `u64::MAX` if no event has been let through yet.
+    last_emitted: AtomicU64,
+    suppressed: AtomicU64,
+}
+
+impl Throttle {
+    /// Create a new throttle. The first event is always let through.
+    pub const fn new() -> Self {
+        Self {
+            last_emitted: AtomicU64::new(u64::MAX),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Check if an event should be emitted, given that at most one event should
+    /// be emitted every `interval`.
+    ///
+    /// It returns `None` if the event should be suppressed.
+    /// Otherwise, it returns the number of events that were suppressed since the
+    /// last one that was let through.
+    pub fn check(&self, interval: Duration) -> Option<u64> {
+        let now = u64::try_from(epoch().elapsed().as_nanos()).unwrap_or(u64::MAX - 1);
+        let interval = u64::try_from(interval.as_nanos()).unwrap_or(u64::MAX);
+        let last_emitted = self.last_emitted.load(Ordering::Relaxed);
+        let is_due = last_emitted == u64::MAX || now.saturating_sub(last_emitted) >= interval;
+        // If another thread got there first, it's the one emitting the event.
+        if !is_due
+            || self
+                .last_emitted
+                .compare_exchange(last_emitted, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(self.suppressed.swap(0, Ordering::Relaxed))
+    }
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The reference point for the timestamps stored in [`Throttle`].
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttle() {
+        let throttle = Throttle::new();
+        let hour = Duration::from_secs(3600);
+        assert_eq!(throttle.check(hour), Some(0));
+        assert_eq!(throttle.check(hour), None);
+        assert_eq!(throttle.check(hour), None);
+        assert_eq!(throttle.check(Duration::ZERO), Some(2));
+        assert_eq!(throttle.check(Duration::ZERO), Some(0));
+    }
+}
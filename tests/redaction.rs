@@ -0,0 +1,107 @@
+//! The redactor is global and can only be registered once: it gets its own test binary,
+//! to keep it away from the fields checked by the other tests.
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_log_error::{fields, log_error, ErrorReport};
+
+#[derive(Debug)]
+struct Chained {
+    message: &'static str,
+    source: Option<Box<Chained>>,
+}
+
+impl std::fmt::Display for Chained {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message)
+    }
+}
+
+impl std::error::Error for Chained {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|s| s as _)
+    }
+}
+
+/// Keeps the `Debug` representation of every field it's given.
+#[derive(Default)]
+struct Capture(Arc<Mutex<Vec<(String, String)>>>);
+
+impl Visit for Capture {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let value = format!("{:?}", value);
+        self.0
+            .lock()
+            .unwrap()
+            .push((field.name().to_owned(), value));
+    }
+}
+
+impl Subscriber for Capture {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        event.record(&mut Capture(self.0.clone()));
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[test]
+fn redaction() {
+    assert!(fields::set_redactor(Box::new(|s: &str| s.replace("hunter2", "[REDACTED]"))).is_ok());
+    let e = Chained {
+        message: "hunter2 was rejected",
+        source: Some(Box::new(Chained {
+            message: "postgres://admin:hunter2@db",
+            source: None,
+        })),
+    };
+
+    let report = ErrorReport::new(&e);
+    assert_eq!(report.message(), "[REDACTED] was rejected");
+    assert!(!report.details().contains("hunter2"));
+    assert!(report.details().contains("[REDACTED]"));
+    assert_eq!(report.source_chain(), "- postgres://admin:[REDACTED]@db\n");
+    assert_eq!(
+        fields::error_source_chain_vec(&e),
+        vec!["postgres://admin:[REDACTED]@db"]
+    );
+    assert_eq!(
+        fields::error_root_cause(&e),
+        "postgres://admin:[REDACTED]@db"
+    );
+    assert!(!fields::render_report(&e).contains("hunter2"));
+    #[cfg(feature = "json")]
+    assert!(!fields::error_json(&e).contains("hunter2"));
+
+    let captured = Capture::default();
+    let recorded = captured.0.clone();
+    tracing::subscriber::with_default(captured, || {
+        log_error!(e, pretty: true);
+    });
+    let recorded = recorded.lock().unwrap();
+    assert!(recorded.iter().all(|(_, value)| !value.contains("hunter2")));
+    #[cfg(not(all(feature = "disable-in-release", not(debug_assertions))))]
+    {
+        let (_, details) = recorded
+            .iter()
+            .find(|(name, _)| name == fields::ERROR_DETAILS)
+            .unwrap();
+        assert!(details.contains("[REDACTED]"));
+    }
+}